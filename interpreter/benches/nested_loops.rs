@@ -0,0 +1,30 @@
+//! Runs `resources/program_with_nested_loops.txt` with one `Interpreter`. The README lists the time per run
+//! before and after instructions were lowered into opcodes, update it when the figure changes noticeably.
+
+use interpreter_app::{ByteCode, Interpreter};
+use std::time::Instant;
+
+const RUNS: u32 = 2000;
+
+fn main() {
+    let source_file = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/program_with_nested_loops.txt");
+    let byte_code = ByteCode::parse_file(source_file).expect("Unable to parse benchmark program");
+    let mut interpreter = Interpreter::new(byte_code);
+
+    // Warm up caches and the allocator before measuring.
+    for _ in 0..RUNS / 10 {
+        assert_eq!(interpreter.interpret(), Ok(Some(1110)));
+    }
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        assert_eq!(interpreter.interpret(), Ok(Some(1110)));
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "program_with_nested_loops.txt: {:?} per run, {} runs in {:?}",
+        elapsed / RUNS,
+        RUNS,
+        elapsed
+    );
+}
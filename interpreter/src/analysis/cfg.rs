@@ -0,0 +1,264 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use super::{flow, Flow};
+use crate::bytecode::ByteCode;
+
+/// Instructions that always run one after another, from `start` up to but not including `end`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+    /// Labels pointing to the first instruction, sorted.
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EdgeKind {
+    /// Execution continues with the next block.
+    FallThrough,
+    /// Jump to a label, taken always by `JUMP` and depending on the popped value by the conditional jumps.
+    Branch,
+    /// `CALL` of a function, the caller continues after `RET`.
+    Call,
+    /// `SPAWN` of a new task.
+    Spawn,
+}
+
+/// Edge between blocks, by block index.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+}
+
+impl ControlFlowGraph {
+    /// Splits the program into basic blocks at labels and after jumps, `RETURN_VALUE` and `RET`.
+    pub fn new(bytecode: &ByteCode) -> Self {
+        let instructions = bytecode.instructions();
+        let mut leaders = BTreeSet::new();
+        if !instructions.is_empty() {
+            leaders.insert(0);
+        }
+        for &target in bytecode.labels().values() {
+            leaders.insert(target as usize);
+        }
+        for (index, instruction) in instructions.iter().enumerate() {
+            match flow(instruction) {
+                Flow::Jump(_) | Flow::Branch(_) | Flow::Return | Flow::Ret => {
+                    leaders.insert(index + 1);
+                }
+                Flow::Next | Flow::Call(_) | Flow::Spawn(_) => {}
+            }
+        }
+        let starts: Vec<usize> = leaders.into_iter().filter(|start| *start < instructions.len()).collect();
+
+        let blocks: Vec<BasicBlock> = starts
+            .iter()
+            .enumerate()
+            .map(|(block, &start)| {
+                let mut labels: Vec<String> = bytecode
+                    .labels()
+                    .iter()
+                    .filter(|(_, target)| **target as usize == start)
+                    .map(|(label, _)| label.clone())
+                    .collect();
+                labels.sort();
+                BasicBlock {
+                    start,
+                    end: starts.get(block + 1).copied().unwrap_or(instructions.len()),
+                    labels,
+                }
+            })
+            .collect();
+
+        let block_of = |index: usize| starts.partition_point(|start| *start <= index) - 1;
+        let mut edges = Vec::new();
+        for (from, block) in blocks.iter().enumerate() {
+            let mut add_edge = |to: usize, kind: EdgeKind| {
+                if to < instructions.len() {
+                    edges.push(Edge { from, to: block_of(to), kind });
+                }
+            };
+            for instruction in &instructions[block.start..block.end] {
+                match flow(instruction) {
+                    Flow::Call(target) => add_edge(target, EdgeKind::Call),
+                    Flow::Spawn(target) => add_edge(target, EdgeKind::Spawn),
+                    _ => {}
+                }
+            }
+            match flow(&instructions[block.end - 1]) {
+                Flow::Next | Flow::Call(_) | Flow::Spawn(_) => add_edge(block.end, EdgeKind::FallThrough),
+                Flow::Jump(target) => add_edge(target, EdgeKind::Branch),
+                Flow::Branch(target) => {
+                    add_edge(block.end, EdgeKind::FallThrough);
+                    add_edge(target, EdgeKind::Branch);
+                }
+                Flow::Return | Flow::Ret => {}
+            }
+        }
+        ControlFlowGraph { blocks, edges }
+    }
+
+    /// Index of the block containing the instruction.
+    pub fn block_of(&self, instruction_index: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| block.start <= instruction_index && instruction_index < block.end)
+    }
+
+    /// Blocks that are the target of a back edge of a depth-first search from every block in order,
+    /// only following fall-through and branch edges. These are the first blocks of loops.
+    pub fn loop_headers(&self) -> BTreeSet<usize> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Visit {
+            New,
+            OnStack,
+            Done,
+        }
+        let mut visits = vec![Visit::New; self.blocks.len()];
+        let mut headers = BTreeSet::new();
+        for root in 0..self.blocks.len() {
+            if visits[root] != Visit::New {
+                continue;
+            }
+            visits[root] = Visit::OnStack;
+            let mut stack = vec![(root, self.local_successors(root))];
+            while let Some((block, successors)) = stack.last_mut() {
+                match successors.pop() {
+                    Some(successor) => match visits[successor] {
+                        Visit::New => {
+                            visits[successor] = Visit::OnStack;
+                            stack.push((successor, self.local_successors(successor)));
+                        }
+                        Visit::OnStack => {
+                            headers.insert(successor);
+                        }
+                        Visit::Done => {}
+                    },
+                    None => {
+                        visits[*block] = Visit::Done;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        headers
+    }
+
+    fn local_successors(&self, block: usize) -> Vec<usize> {
+        self.edges
+            .iter()
+            .rev()
+            .filter(|edge| edge.from == block && matches!(edge.kind, EdgeKind::FallThrough | EdgeKind::Branch))
+            .map(|edge| edge.to)
+            .collect()
+    }
+
+    /// Graphviz source of the graph. Every block lists its labels and instructions with their numbers,
+    /// loop headers are drawn with a double border.
+    pub fn to_dot(&self, bytecode: &ByteCode) -> String {
+        let loop_headers = self.loop_headers();
+        let mut dot = String::from("digraph bytecode {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for (index, block) in self.blocks.iter().enumerate() {
+            let mut label = String::new();
+            for block_label in &block.labels {
+                let _ = write!(label, "{}\\l", block_label);
+            }
+            for instruction_index in block.start..block.end {
+                let instruction = bytecode.get_instruction(instruction_index).unwrap();
+                let _ = write!(label, "#{}  {}\\l", instruction_index + 1, instruction);
+            }
+            let _ = write!(dot, "    block{} [label=\"{}\"", index, label.replace('"', "\\\""));
+            if loop_headers.contains(&index) {
+                dot.push_str(", peripheries=2");
+            }
+            dot.push_str("];\n");
+        }
+        for edge in &self.edges {
+            let attributes = match edge.kind {
+                EdgeKind::FallThrough => "",
+                EdgeKind::Branch => " [label=\"taken\"]",
+                EdgeKind::Call => " [label=\"call\", style=dashed]",
+                EdgeKind::Spawn => " [label=\"spawn\", style=dotted]",
+            };
+            let _ = writeln!(dot, "    block{} -> block{}{};", edge.from, edge.to, attributes);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn graph(code: &str) -> (ByteCode, ControlFlowGraph) {
+        let bytecode = ByteCode::parse_instructions(&mut BufReader::new(code.as_bytes())).unwrap();
+        let graph = ControlFlowGraph::new(&bytecode);
+        (bytecode, graph)
+    }
+
+    #[test]
+    fn build_blocks_and_edges() {
+        let code = "LOAD_VAL 1\nJUMP_IF_TRUE .skip\nLOAD_VAL 2\nRETURN_VALUE\n.skip\nCALL .f\nRETURN_VALUE\n.f\nRET";
+        let (_, graph) = graph(code);
+        assert_eq!(
+            graph.blocks,
+            vec![
+                BasicBlock { start: 0, end: 2, labels: vec![] },
+                BasicBlock { start: 2, end: 4, labels: vec![] },
+                BasicBlock { start: 4, end: 6, labels: vec![".skip".to_string()] },
+                BasicBlock { start: 6, end: 7, labels: vec![".f".to_string()] },
+            ]
+        );
+        assert_eq!(
+            graph.edges,
+            vec![
+                Edge { from: 0, to: 1, kind: EdgeKind::FallThrough },
+                Edge { from: 0, to: 2, kind: EdgeKind::Branch },
+                Edge { from: 2, to: 3, kind: EdgeKind::Call },
+            ]
+        );
+        assert_eq!(graph.block_of(5), Some(2));
+        assert_eq!(graph.block_of(7), None);
+    }
+
+    #[test]
+    fn find_loop_headers_of_nested_loops() {
+        let file = File::open("resources/program_with_nested_loops.txt").unwrap();
+        let bytecode = ByteCode::parse_instructions(&mut BufReader::new(file)).unwrap();
+        let graph = ControlFlowGraph::new(&bytecode);
+        let headers: Vec<&[String]> = graph
+            .loop_headers()
+            .into_iter()
+            .map(|block| graph.blocks[block].labels.as_slice())
+            .collect();
+        assert_eq!(headers, vec![[".first".to_string()], [".second".to_string()], [".third".to_string()]]);
+    }
+
+    #[test]
+    fn export_dot() {
+        let (bytecode, graph) = graph(".loop\nLOAD_VAL 1\nGOTO .loop\nSPAWN .worker\n.worker\nRECV_CHANNEL ch");
+        assert_eq!(
+            graph.to_dot(&bytecode),
+            "digraph bytecode {\n    node [shape=box, fontname=\"monospace\"];\n\
+            \x20   block0 [label=\".loop\\l#1  LOAD_VAL 1\\l#2  GOTO .loop\\l\", peripheries=2];\n\
+            \x20   block1 [label=\"#3  SPAWN .worker\\l\"];\n\
+            \x20   block2 [label=\".worker\\l#4  RECV_CHANNEL ch\\l\"];\n\
+            \x20   block0 -> block1;\n\
+            \x20   block0 -> block0 [label=\"taken\"];\n\
+            \x20   block1 -> block2 [label=\"spawn\", style=dotted];\n\
+            \x20   block1 -> block2;\n\
+            }\n"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+pub mod cfg;
+pub mod stack;
+pub mod variables;
+
+use crate::bytecode::instruction::Instruction;
+use crate::config::InstructionName;
+
+/// Where execution goes after an instruction, within the task that executes it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Flow {
+    /// Continues with the next instruction.
+    Next,
+    /// Always continues at the target.
+    Jump(usize),
+    /// Continues either at the target or with the next instruction.
+    Branch(usize),
+    /// Runs the function at the target, then continues with the next instruction if it returns.
+    Call(usize),
+    /// Starts a new task at the target and continues with the next instruction.
+    Spawn(usize),
+    /// `RETURN_VALUE`, finishes the task.
+    Return,
+    /// `RET`, returns to the caller.
+    Ret,
+}
+
+/// Expects resolved label targets, an instruction with an undefined label continues with the next instruction.
+pub fn flow(instruction: &Instruction) -> Flow {
+    let target = match instruction.target {
+        Some(target) => target as usize,
+        None => {
+            return match instruction.name {
+                InstructionName::RETURN => Flow::Return,
+                InstructionName::RET => Flow::Ret,
+                _ => Flow::Next,
+            }
+        }
+    };
+    match instruction.name {
+        InstructionName::JUMP => Flow::Jump(target),
+        InstructionName::GOTO | InstructionName::JUMPIF | InstructionName::JUMPIFNOT => Flow::Branch(target),
+        InstructionName::CALL => Flow::Call(target),
+        InstructionName::SPAWN => Flow::Spawn(target),
+        _ => Flow::Next,
+    }
+}
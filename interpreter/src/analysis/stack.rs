@@ -0,0 +1,373 @@
+use std::collections::{HashMap, HashSet};
+use super::{flow, Flow};
+use crate::bytecode::ByteCode;
+use crate::config::InstructionName;
+use crate::error::{VerifyError, VerifyErrorKind};
+
+/// Number of times a recursive function is analyzed before its stack effect is considered unbounded.
+const MAX_RECURSION_PASSES: usize = 8;
+/// Number of distinct functions analyzed inside each other before the chain of `CALL`s is rejected. Every level
+/// takes a few kilobytes of the Rust stack, this keeps `verify_stack` within a 2 MiB thread stack.
+const MAX_NESTED_FUNCTIONS: usize = 256;
+
+/// Values the instruction pops and pushes, the effect of `CALL` depends on the called function.
+pub fn stack_effect(instruction_name: InstructionName) -> (usize, usize) {
+    match instruction_name {
+        InstructionName::LOAD | InstructionName::READ | InstructionName::RECV => (0, 1),
+        InstructionName::WRITE
+        | InstructionName::POP
+        | InstructionName::SEND
+        | InstructionName::RETURN
+        | InstructionName::GOTO
+        | InstructionName::JUMPIF
+        | InstructionName::JUMPIFNOT => (1, 0),
+        InstructionName::ADD
+        | InstructionName::SUB
+        | InstructionName::MULTIPLY
+        | InstructionName::DIV
+        | InstructionName::MOD
+        | InstructionName::AND
+        | InstructionName::OR
+        | InstructionName::XOR
+        | InstructionName::SHL
+        | InstructionName::SHR
+        | InstructionName::EQUAL
+        | InstructionName::GREATER
+        | InstructionName::LESS => (2, 1),
+        InstructionName::NEG | InstructionName::NOT => (1, 1),
+        InstructionName::DUP => (1, 2),
+        InstructionName::JUMP | InstructionName::SPAWN | InstructionName::CALL | InstructionName::RET => (0, 0),
+    }
+}
+
+/// Stack effect of a function that returns with `RET`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FunctionSummary {
+    /// Number of the caller's values the function pops.
+    pub arguments: usize,
+    /// Change of the stack depth between `CALL` and the return.
+    pub effect: i64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Context {
+    /// Code of a task, the stack starts empty and `RET` is an error.
+    Task,
+    /// Body of a function, depths are relative to the depth at `CALL` and may be negative.
+    Function,
+}
+
+enum SummaryState {
+    /// Being analyzed, recursive calls use the summary of the previous pass.
+    InProgress(Option<FunctionSummary>),
+    Done(Option<FunctionSummary>),
+}
+
+struct Body {
+    min_depth: i64,
+    return_depth: Option<i64>,
+    errors: Vec<VerifyError>,
+}
+
+struct Verifier<'a> {
+    bytecode: &'a ByteCode,
+    /// `None` for functions that never return.
+    summaries: HashMap<usize, SummaryState>,
+    /// Functions called while they were being analyzed.
+    recursive: HashSet<usize>,
+    /// Functions being analyzed.
+    nesting: usize,
+    /// Targets of `SPAWN`, each one starts a task with an empty stack.
+    spawned: Vec<usize>,
+    errors: Vec<VerifyError>,
+}
+
+/// Computes the stack depth at every reachable instruction and rejects programs that can pop from an empty stack,
+/// reach an instruction with different depths or return from a function with different depths.
+/// Tasks start at the first instruction and at every `SPAWN` target; `CALL` uses the stack effect of the function.
+pub fn verify_stack(bytecode: &ByteCode) -> Result<(), Vec<VerifyError>> {
+    let mut verifier = Verifier {
+        bytecode,
+        summaries: HashMap::new(),
+        recursive: HashSet::new(),
+        nesting: 0,
+        spawned: vec![0],
+        errors: Vec::new(),
+    };
+    let mut tasks = HashSet::new();
+    while let Some(entry) = verifier.spawned.pop() {
+        if tasks.insert(entry) {
+            let body = verifier.analyze(entry, Context::Task);
+            verifier.errors.extend(body.errors);
+        }
+    }
+    let mut errors = verifier.errors;
+    if errors.is_empty() {
+        return Ok(());
+    }
+    errors.sort_by_key(|error| error.instruction_index);
+    errors.dedup();
+    Err(errors)
+}
+
+impl<'a> Verifier<'a> {
+    fn analyze(&mut self, entry: usize, context: Context) -> Body {
+        let instructions = self.bytecode.instructions();
+        let mut body = Body {
+            min_depth: 0,
+            return_depth: None,
+            errors: Vec::new(),
+        };
+        if entry >= instructions.len() {
+            return body;
+        }
+        // Only the instructions of this body, so nested analyses of a long chain of calls don't each hold
+        // a depth for every instruction of the program.
+        let mut depths: HashMap<usize, i64> = HashMap::from([(entry, 0)]);
+        let mut reported = HashSet::new();
+        let mut worklist = vec![entry];
+        while let Some(index) = worklist.pop() {
+            let instruction = &instructions[index];
+            let depth = depths[&index];
+            let flow = flow(instruction);
+            let (pops, pushes) = match flow {
+                Flow::Call(target) => match self.summary(target) {
+                    Some(summary) => (summary.arguments, summary.arguments as i64 + summary.effect),
+                    None => continue,
+                },
+                _ => {
+                    let (pops, pushes) = stack_effect(instruction.name);
+                    (pops, pushes as i64)
+                }
+            };
+            if depth < pops as i64 {
+                match context {
+                    Context::Task => {
+                        let kind = VerifyErrorKind::StackUnderflow { needed: pops, available: depth };
+                        body.errors.push(self.error(index, kind));
+                        continue;
+                    }
+                    Context::Function => body.min_depth = body.min_depth.min(depth - pops as i64),
+                }
+            }
+            let next_depth = depth - pops as i64 + pushes;
+
+            let successors = match flow {
+                Flow::Next | Flow::Call(_) => vec![index + 1],
+                Flow::Jump(target) => vec![target],
+                Flow::Branch(target) => vec![index + 1, target],
+                Flow::Spawn(target) => {
+                    self.spawned.push(target);
+                    vec![index + 1]
+                }
+                Flow::Return => vec![],
+                Flow::Ret => {
+                    match (context, body.return_depth) {
+                        (Context::Task, _) => body.errors.push(self.error(index, VerifyErrorKind::RetOutsideCall)),
+                        (Context::Function, None) => body.return_depth = Some(next_depth),
+                        (Context::Function, Some(return_depth)) if return_depth != next_depth => {
+                            let kind = VerifyErrorKind::InconsistentReturn(return_depth, next_depth);
+                            body.errors.push(self.error(index, kind));
+                        }
+                        (Context::Function, Some(_)) => {}
+                    }
+                    vec![]
+                }
+            };
+            for successor in successors {
+                // Running past the last instruction finishes the task.
+                if successor >= instructions.len() {
+                    continue;
+                }
+                match depths.get(&successor).copied() {
+                    None => {
+                        depths.insert(successor, next_depth);
+                        worklist.push(successor);
+                    }
+                    Some(successor_depth) if successor_depth != next_depth && reported.insert(successor) => {
+                        let kind = VerifyErrorKind::InconsistentDepth(successor_depth, next_depth);
+                        body.errors.push(self.error(successor, kind));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        body
+    }
+
+    /// Analyzes the function at `target` on its first call. A recursive function is analyzed again
+    /// with the summary of the previous pass until the summary doesn't change.
+    fn summary(&mut self, target: usize) -> Option<FunctionSummary> {
+        match self.summaries.get(&target) {
+            Some(SummaryState::Done(summary)) => return *summary,
+            Some(SummaryState::InProgress(summary)) => {
+                self.recursive.insert(target);
+                return *summary;
+            }
+            None => {}
+        }
+        if self.nesting >= MAX_NESTED_FUNCTIONS {
+            let error = self.error(target, VerifyErrorKind::CallsTooDeep(MAX_NESTED_FUNCTIONS));
+            self.errors.push(error);
+            self.summaries.insert(target, SummaryState::Done(None));
+            return None;
+        }
+        let mut provisional = None;
+        for _ in 0..MAX_RECURSION_PASSES {
+            self.summaries.insert(target, SummaryState::InProgress(provisional));
+            self.recursive.remove(&target);
+            self.nesting += 1;
+            let body = self.analyze(target, Context::Function);
+            self.nesting -= 1;
+            let summary = body.return_depth.map(|effect| FunctionSummary {
+                arguments: (-body.min_depth) as usize,
+                effect,
+            });
+            if !self.recursive.contains(&target) || summary == provisional {
+                self.errors.extend(body.errors);
+                self.summaries.insert(target, SummaryState::Done(summary));
+                return summary;
+            }
+            provisional = summary;
+        }
+        let error = self.error(target, VerifyErrorKind::UnboundedRecursion);
+        self.errors.push(error);
+        self.summaries.insert(target, SummaryState::Done(None));
+        None
+    }
+
+    fn error(&self, index: usize, kind: VerifyErrorKind) -> VerifyError {
+        VerifyError {
+            instruction_index: index,
+            instruction: self.bytecode.instructions()[index].name,
+            position: self.bytecode.get_position(index),
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn verify(code: &str) -> Result<(), Vec<(usize, VerifyErrorKind)>> {
+        let bytecode = ByteCode::parse_instructions(&mut BufReader::new(code.as_bytes())).unwrap();
+        verify_stack(&bytecode).map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|error| (error.instruction_index, error.kind))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn verify_resource_programs() {
+        for path in ["resources/simple_program.txt", "resources/program_with_nested_loops.txt"] {
+            let file = File::open(path).unwrap();
+            let bytecode = ByteCode::parse_instructions(&mut BufReader::new(file)).unwrap();
+            assert_eq!(verify_stack(&bytecode), Ok(()));
+        }
+    }
+
+    #[test]
+    fn verify_stack_underflow() {
+        assert_eq!(
+            verify("LOAD_VAL 1\nADD\nRETURN_VALUE"),
+            Err(vec![(1, VerifyErrorKind::StackUnderflow { needed: 2, available: 1 })])
+        );
+    }
+
+    #[test]
+    fn verify_underflow_on_one_path() {
+        let code = "LOAD_VAL 1\nJUMP_IF_TRUE .skip\nLOAD_VAL 2\n.skip\nRETURN_VALUE";
+        assert_eq!(
+            verify(code),
+            Err(vec![
+                (3, VerifyErrorKind::StackUnderflow { needed: 1, available: 0 }),
+                (3, VerifyErrorKind::InconsistentDepth(0, 1)),
+            ])
+        );
+    }
+
+    #[test]
+    fn verify_loop_growing_the_stack() {
+        let code = "LOAD_VAL 0\n.loop\nLOAD_VAL 1\nDUP\nGOTO .loop\nRETURN_VALUE";
+        assert_eq!(
+            verify(code),
+            Err(vec![(1, VerifyErrorKind::InconsistentDepth(1, 2))])
+        );
+    }
+
+    #[test]
+    fn verify_function_calls() {
+        let code = "LOAD_VAL 3\nCALL .square\nRETURN_VALUE\n.square\nWRITE_VAR n\nREAD_VAR n\nREAD_VAR n\nMULTIPLY\nRET";
+        assert_eq!(verify(code), Ok(()));
+
+        let code = "CALL .square\nRETURN_VALUE\n.square\nWRITE_VAR n\nREAD_VAR n\nREAD_VAR n\nMULTIPLY\nRET";
+        assert_eq!(
+            verify(code),
+            Err(vec![(0, VerifyErrorKind::StackUnderflow { needed: 1, available: 0 })])
+        );
+    }
+
+    #[test]
+    fn verify_recursive_functions() {
+        let code = "LOAD_VAL 5\nCALL .fact\nRETURN_VALUE\n\
+            .fact\nDUP\nLOAD_VAL 1\nLESS\nJUMP_IF_TRUE .recurse\nRET\n\
+            .recurse\nWRITE_VAR n\nREAD_VAR n\nLOAD_VAL 1\nREAD_VAR n\nSUB\nCALL .fact\nMULTIPLY\nRET";
+        assert_eq!(verify(code), Ok(()));
+
+        let code = "CALL .f\nRETURN_VALUE\n.f\nLOAD_VAL 1\nJUMP_IF_TRUE .deeper\nRET\n.deeper\nLOAD_VAL 1\nCALL .f\nRET";
+        assert_eq!(
+            verify(code),
+            Err(vec![(2, VerifyErrorKind::UnboundedRecursion)])
+        );
+    }
+
+    #[test]
+    fn verify_deeply_nested_calls() {
+        let chain = |functions: usize| {
+            let mut code = "CALL .f0\nLOAD_VAL 1\nRETURN_VALUE\n".to_string();
+            for index in 0..functions - 1 {
+                code.push_str(&format!(".f{}\nCALL .f{}\nRET\n", index, index + 1));
+            }
+            code.push_str(&format!(".f{}\nRET", functions - 1));
+            verify(&code)
+        };
+        assert_eq!(chain(MAX_NESTED_FUNCTIONS), Ok(()));
+        let too_deep = 3 + 2 * MAX_NESTED_FUNCTIONS;
+        assert_eq!(
+            chain(MAX_NESTED_FUNCTIONS + 1),
+            Err(vec![(too_deep, VerifyErrorKind::CallsTooDeep(MAX_NESTED_FUNCTIONS))])
+        );
+        assert_eq!(
+            chain(20000),
+            Err(vec![(too_deep, VerifyErrorKind::CallsTooDeep(MAX_NESTED_FUNCTIONS))])
+        );
+    }
+
+    #[test]
+    fn verify_inconsistent_returns() {
+        let code = "LOAD_VAL 1\nCALL .f\nRETURN_VALUE\n.f\nJUMP_IF_TRUE .one\nRET\n.one\nLOAD_VAL 1\nRET";
+        assert_eq!(
+            verify(code),
+            Err(vec![(4, VerifyErrorKind::InconsistentReturn(0, -1))])
+        );
+    }
+
+    #[test]
+    fn verify_ret_outside_call() {
+        assert_eq!(verify("LOAD_VAL 1\nRET"), Err(vec![(1, VerifyErrorKind::RetOutsideCall)]));
+    }
+
+    #[test]
+    fn verify_spawned_task_starts_with_empty_stack() {
+        let code = "LOAD_VAL 1\nSPAWN .worker\nRETURN_VALUE\n.worker\nSEND_CHANNEL out";
+        assert_eq!(
+            verify(code),
+            Err(vec![(3, VerifyErrorKind::StackUnderflow { needed: 1, available: 0 })])
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use super::{flow, Flow};
+use crate::bytecode::{ByteCode, SourcePosition};
+use crate::config::InstructionName;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VariableDiagnosticKind {
+    /// `READ_VAR` of a variable that isn't written on any path, always fails at runtime.
+    ReadBeforeWrite(String),
+    /// `READ_VAR` of a variable that is written on some paths only.
+    MaybeReadBeforeWrite(String),
+    /// `WRITE_VAR` of a variable that is never read in the same frame, reported at the first write.
+    UnusedWrite(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct VariableDiagnostic {
+    pub instruction_index: usize,
+    pub position: Option<SourcePosition>,
+    pub severity: Severity,
+    pub kind: VariableDiagnosticKind,
+}
+
+impl fmt::Display for VariableDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: instruction #{}", severity, self.instruction_index + 1)?;
+        if let Some(position) = self.position {
+            write!(f, " (line {}, column {})", position.line, position.column)?;
+        }
+        match &self.kind {
+            VariableDiagnosticKind::ReadBeforeWrite(name) => {
+                write!(f, ": variable {} is read before it is written", name)
+            }
+            VariableDiagnosticKind::MaybeReadBeforeWrite(name) => {
+                write!(f, ": variable {} may be read before it is written", name)
+            }
+            VariableDiagnosticKind::UnusedWrite(name) => write!(f, ": variable {} is written but never read", name),
+        }
+    }
+}
+
+/// Variables written before an instruction: on every path (`must`) and on some path (`may`).
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Assigned<'a> {
+    must: BTreeSet<&'a str>,
+    may: BTreeSet<&'a str>,
+}
+
+/// Finds `READ_VAR`s that can run before the variable is written and variables that are written but never read.
+/// Every task and every function call starts with no variables, so the code reachable from the first instruction,
+/// from each `SPAWN` target and from each `CALL` target is checked separately; `CALL` doesn't change the caller's
+/// variables. Diagnostics are sorted by instruction.
+pub fn check_variables(bytecode: &ByteCode) -> Vec<VariableDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut entries = vec![0];
+    let mut checked = HashSet::new();
+    while let Some(entry) = entries.pop() {
+        if entry < bytecode.instructions().len() && checked.insert(entry) {
+            check_frame(bytecode, entry, &mut entries, &mut diagnostics);
+        }
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.instruction_index);
+    diagnostics.dedup();
+    diagnostics
+}
+
+/// Checks the code reachable from `entry` within one frame and adds the entries of the frames it starts.
+fn check_frame<'a>(
+    bytecode: &'a ByteCode,
+    entry: usize,
+    entries: &mut Vec<usize>,
+    diagnostics: &mut Vec<VariableDiagnostic>,
+) {
+    let instructions = bytecode.instructions();
+    let mut assigned: Vec<Option<Assigned<'a>>> = vec![None; instructions.len()];
+    assigned[entry] = Some(Assigned {
+        must: BTreeSet::new(),
+        may: BTreeSet::new(),
+    });
+    let mut worklist = vec![entry];
+    while let Some(index) = worklist.pop() {
+        let instruction = &instructions[index];
+        let mut after = assigned[index].clone().unwrap();
+        if instruction.name == InstructionName::WRITE {
+            let name = instruction.variable.as_deref().unwrap();
+            after.must.insert(name);
+            after.may.insert(name);
+        }
+        let successors = match flow(instruction) {
+            Flow::Next => vec![index + 1],
+            Flow::Jump(target) => vec![target],
+            Flow::Branch(target) => vec![index + 1, target],
+            Flow::Call(target) | Flow::Spawn(target) => {
+                entries.push(target);
+                vec![index + 1]
+            }
+            Flow::Return | Flow::Ret => vec![],
+        };
+        for successor in successors.into_iter().filter(|successor| *successor < instructions.len()) {
+            let merged = match &assigned[successor] {
+                None => after.clone(),
+                Some(before) => Assigned {
+                    must: before.must.intersection(&after.must).copied().collect(),
+                    may: before.may.union(&after.may).copied().collect(),
+                },
+            };
+            if assigned[successor].as_ref() != Some(&merged) {
+                assigned[successor] = Some(merged);
+                worklist.push(successor);
+            }
+        }
+    }
+
+    let reads: HashSet<&str> = instructions
+        .iter()
+        .zip(&assigned)
+        .filter(|(instruction, before)| before.is_some() && instruction.name == InstructionName::READ)
+        .map(|(instruction, _)| instruction.variable.as_deref().unwrap())
+        .collect();
+    let mut unused_writes = HashSet::new();
+    for (index, (instruction, before)) in instructions.iter().zip(&assigned).enumerate() {
+        let before = match before {
+            Some(before) => before,
+            None => continue,
+        };
+        let name = match instruction.variable.as_deref() {
+            Some(name) => name,
+            None => continue,
+        };
+        let (severity, kind) = match instruction.name {
+            InstructionName::READ if before.may.contains(name) && !before.must.contains(name) => {
+                (Severity::Warning, VariableDiagnosticKind::MaybeReadBeforeWrite(name.to_string()))
+            }
+            InstructionName::READ if !before.may.contains(name) => {
+                (Severity::Error, VariableDiagnosticKind::ReadBeforeWrite(name.to_string()))
+            }
+            InstructionName::WRITE if !reads.contains(name) && unused_writes.insert(name) => {
+                (Severity::Warning, VariableDiagnosticKind::UnusedWrite(name.to_string()))
+            }
+            _ => continue,
+        };
+        diagnostics.push(VariableDiagnostic {
+            instruction_index: index,
+            position: bytecode.get_position(index),
+            severity,
+            kind,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn check(code: &str) -> Vec<(usize, Severity, VariableDiagnosticKind)> {
+        let bytecode = ByteCode::parse_instructions(&mut BufReader::new(code.as_bytes())).unwrap();
+        check_variables(&bytecode)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.instruction_index, diagnostic.severity, diagnostic.kind))
+            .collect()
+    }
+
+    #[test]
+    fn check_resource_programs() {
+        for path in ["resources/simple_program.txt", "resources/program_with_nested_loops.txt"] {
+            let file = File::open(path).unwrap();
+            let bytecode = ByteCode::parse_instructions(&mut BufReader::new(file)).unwrap();
+            assert_eq!(check_variables(&bytecode), vec![]);
+        }
+    }
+
+    #[test]
+    fn check_read_before_write() {
+        assert_eq!(
+            check("READ_VAR x\nWRITE_VAR x\nREAD_VAR x\nRETURN_VALUE"),
+            vec![(0, Severity::Error, VariableDiagnosticKind::ReadBeforeWrite("x".to_string()))]
+        );
+    }
+
+    #[test]
+    fn check_read_written_on_one_branch() {
+        let code = "LOAD_VAL 1\nJUMP_IF_TRUE .skip\nLOAD_VAL 2\nWRITE_VAR x\n.skip\nREAD_VAR x\nRETURN_VALUE";
+        assert_eq!(
+            check(code),
+            vec![(4, Severity::Warning, VariableDiagnosticKind::MaybeReadBeforeWrite("x".to_string()))]
+        );
+    }
+
+    #[test]
+    fn check_variable_written_in_loop() {
+        let code = "LOAD_VAL 0\nWRITE_VAR i\n.loop\nREAD_VAR i\nLOAD_VAL 1\nADD\nDUP\nWRITE_VAR i\nGOTO .loop\n\
+            READ_VAR i\nRETURN_VALUE";
+        assert_eq!(check(code), vec![]);
+    }
+
+    #[test]
+    fn check_unused_write() {
+        assert_eq!(
+            check("LOAD_VAL 1\nWRITE_VAR x\nLOAD_VAL 2\nWRITE_VAR x\nLOAD_VAL 3\nRETURN_VALUE"),
+            vec![(1, Severity::Warning, VariableDiagnosticKind::UnusedWrite("x".to_string()))]
+        );
+    }
+
+    #[test]
+    fn check_frames_separately() {
+        let code = "LOAD_VAL 2\nWRITE_VAR n\nREAD_VAR n\nCALL .f\nRETURN_VALUE\n\
+            .f\nREAD_VAR n\nRET";
+        assert_eq!(
+            check(code),
+            vec![(5, Severity::Error, VariableDiagnosticKind::ReadBeforeWrite("n".to_string()))]
+        );
+
+        let code = "LOAD_VAL 2\nWRITE_VAR n\nSPAWN .worker\nREAD_VAR n\nRETURN_VALUE\n\
+            .worker\nREAD_VAR n\nSEND_CHANNEL out";
+        assert_eq!(
+            check(code),
+            vec![(5, Severity::Error, VariableDiagnosticKind::ReadBeforeWrite("n".to_string()))]
+        );
+    }
+}
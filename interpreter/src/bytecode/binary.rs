@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use super::instruction::Instruction;
+use super::lexer;
+use super::{ByteCode, SourcePosition, MAX_INSTRUCTIONS};
+use crate::config::{InstructionName, MaxInstructionNumber, VariableValue, SIGNED_VALUES};
+use crate::error::{LoadError, LoadErrorKind};
+
+/// First bytes of every binary ByteCode file.
+pub const MAGIC: [u8; 4] = *b"\x7fBYC";
+pub const FORMAT_VERSION: u16 = 2;
+
+const VALUE_WIDTH: u8 = std::mem::size_of::<VariableValue>() as u8;
+/// Magic, version, value width, signedness, payload length and checksum.
+const HEADER_LENGTH: usize = 4 + 2 + 1 + 1 + 4 + 4;
+
+/// Opcodes of the binary format are indices in this table, new instructions are only appended.
+const OPCODES: [InstructionName; 30] = [
+    InstructionName::LOAD,
+    InstructionName::WRITE,
+    InstructionName::READ,
+    InstructionName::ADD,
+    InstructionName::SUB,
+    InstructionName::MULTIPLY,
+    InstructionName::DIV,
+    InstructionName::MOD,
+    InstructionName::NEG,
+    InstructionName::AND,
+    InstructionName::OR,
+    InstructionName::XOR,
+    InstructionName::NOT,
+    InstructionName::SHL,
+    InstructionName::SHR,
+    InstructionName::RETURN,
+    InstructionName::EQUAL,
+    InstructionName::GREATER,
+    InstructionName::LESS,
+    InstructionName::DUP,
+    InstructionName::POP,
+    InstructionName::GOTO,
+    InstructionName::JUMP,
+    InstructionName::JUMPIF,
+    InstructionName::JUMPIFNOT,
+    InstructionName::SPAWN,
+    InstructionName::SEND,
+    InstructionName::RECV,
+    InstructionName::CALL,
+    InstructionName::RET,
+];
+
+enum Operand {
+    None,
+    /// Index in the constant table.
+    Value,
+    /// Index in the name table of a variable, channel or label.
+    Name,
+}
+
+fn operand(name: InstructionName) -> Operand {
+    match name {
+        InstructionName::LOAD => Operand::Value,
+        InstructionName::WRITE
+        | InstructionName::READ
+        | InstructionName::GOTO
+        | InstructionName::JUMP
+        | InstructionName::JUMPIF
+        | InstructionName::JUMPIFNOT
+        | InstructionName::SPAWN
+        | InstructionName::SEND
+        | InstructionName::RECV
+        | InstructionName::CALL => Operand::Name,
+        _ => Operand::None,
+    }
+}
+
+/// Index of every distinct entry in the order they are first added.
+struct Table<T> {
+    entries: Vec<T>,
+    indices: HashMap<T, u32>,
+}
+
+impl<T: Clone + Eq + std::hash::Hash> Table<T> {
+    fn new() -> Self {
+        Table {
+            entries: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    fn index(&mut self, entry: &T) -> u32 {
+        if let Some(index) = self.indices.get(entry) {
+            return *index;
+        }
+        let index = self.entries.len() as u32;
+        self.entries.push(entry.clone());
+        self.indices.insert(entry.clone(), index);
+        index
+    }
+}
+
+/// FNV-1a, 32 bits.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, byte| (hash ^ u32::from(*byte)).wrapping_mul(0x0100_0193))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, kind: LoadErrorKind) -> LoadError {
+        LoadError::new(kind).at_offset(self.offset)
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], LoadError> {
+        if self.bytes.len() - self.offset < length {
+            return Err(self.error(LoadErrorKind::UnexpectedEnd));
+        }
+        let bytes = &self.bytes[self.offset..self.offset + length];
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, LoadError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, LoadError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn value(&mut self) -> Result<VariableValue, LoadError> {
+        Ok(VariableValue::from_le_bytes(self.take(VALUE_WIDTH as usize)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, LoadError> {
+        let length = self.u32()? as usize;
+        let start = self.offset;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| {
+            LoadError::new(LoadErrorKind::InvalidName(String::from_utf8_lossy(bytes).into_owned())).at_offset(start)
+        })
+    }
+
+    /// Reads an index and returns the table entry.
+    fn entry<T: Clone>(&mut self, table: &[T]) -> Result<T, LoadError> {
+        let start = self.offset;
+        let index = self.u32()?;
+        table
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| LoadError::new(LoadErrorKind::InvalidIndex(index)).at_offset(start))
+    }
+}
+
+impl ByteCode {
+    /// Serializes the program into the binary format: a header with the magic number, the format version, the
+    /// width and signedness of `VariableValue`, the payload length and its checksum, followed by the payload with
+    /// the name table, the constant table, the labels, the instructions and their source positions.
+    /// All numbers are little-endian.
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut labels: Vec<(&String, &MaxInstructionNumber)> = self.labels.iter().collect();
+        labels.sort();
+        let mut names = Table::new();
+        let mut constants = Table::new();
+        let mut code = Vec::new();
+        for instruction in &self.instructions {
+            let opcode = OPCODES.iter().position(|name| *name == instruction.name).unwrap();
+            code.push(opcode as u8);
+            let index = match operand(instruction.name) {
+                Operand::None => continue,
+                Operand::Value => constants.index(&instruction.arg.unwrap()),
+                Operand::Name => names.index(instruction.variable.as_ref().unwrap()),
+            };
+            code.extend(index.to_le_bytes());
+        }
+        let label_names: Vec<u32> = labels.iter().map(|(label, _)| names.index(label)).collect();
+
+        let mut payload = Vec::new();
+        payload.extend((names.entries.len() as u32).to_le_bytes());
+        for name in &names.entries {
+            payload.extend((name.len() as u32).to_le_bytes());
+            payload.extend(name.as_bytes());
+        }
+        payload.extend((constants.entries.len() as u32).to_le_bytes());
+        for value in &constants.entries {
+            payload.extend(value.to_le_bytes());
+        }
+        payload.extend((labels.len() as u32).to_le_bytes());
+        for (name, (_, target)) in label_names.iter().zip(&labels) {
+            payload.extend(name.to_le_bytes());
+            payload.extend(u32::from(**target).to_le_bytes());
+        }
+        payload.extend((self.instructions.len() as u32).to_le_bytes());
+        payload.extend(code);
+        payload.extend((self.positions.len() as u32).to_le_bytes());
+        for position in &self.positions {
+            payload.extend((position.line as u32).to_le_bytes());
+            payload.extend((position.column as u32).to_le_bytes());
+        }
+
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + payload.len());
+        bytes.extend(MAGIC);
+        bytes.extend(FORMAT_VERSION.to_le_bytes());
+        bytes.push(VALUE_WIDTH);
+        bytes.push(SIGNED_VALUES as u8);
+        bytes.extend((payload.len() as u32).to_le_bytes());
+        bytes.extend(checksum(&payload).to_le_bytes());
+        bytes.extend(payload);
+        bytes
+    }
+
+    /// Loads a program written by `to_binary`. The header, the checksum, every table index, opcode, name and label
+    /// are checked before the program is built, so a loaded program can be run and disassembled like a parsed one.
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, LoadError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if !ByteCode::is_binary(bytes) {
+            return Err(LoadError::new(LoadErrorKind::InvalidMagic));
+        }
+        reader.take(MAGIC.len())?;
+        let version = reader.u16()?;
+        if version != FORMAT_VERSION {
+            return Err(LoadError::new(LoadErrorKind::UnsupportedVersion(version)));
+        }
+        let width = reader.u8()?;
+        let signed = reader.u8()? != 0;
+        if width != VALUE_WIDTH || signed != SIGNED_VALUES {
+            return Err(LoadError::new(LoadErrorKind::ValueTypeMismatch { width, signed }));
+        }
+        let length = reader.u32()? as usize;
+        let expected = reader.u32()?;
+        let payload = reader.take(length)?;
+        if reader.offset != bytes.len() {
+            return Err(reader.error(LoadErrorKind::TrailingBytes(bytes.len() - reader.offset)));
+        }
+        let actual = checksum(payload);
+        if actual != expected {
+            return Err(LoadError::new(LoadErrorKind::ChecksumMismatch { expected, actual }));
+        }
+        reader.offset = HEADER_LENGTH;
+
+        let names = (0..reader.u32()?).map(|_| reader.string()).collect::<Result<Vec<_>, _>>()?;
+        let constants = (0..reader.u32()?).map(|_| reader.value()).collect::<Result<Vec<_>, _>>()?;
+        let mut program = ByteCode::new();
+        let mut label_targets = Vec::new();
+        for _ in 0..reader.u32()? {
+            let start = reader.offset;
+            let label = reader.entry(&names)?;
+            if !ByteCode::is_label(&label) {
+                return Err(LoadError::new(LoadErrorKind::InvalidLabelName(label)).at_offset(start));
+            }
+            if program.labels.contains_key(&label) {
+                return Err(LoadError::new(LoadErrorKind::DuplicatedLabel(label)).at_offset(start));
+            }
+            let target = reader.u32()?;
+            let label_target = MaxInstructionNumber::try_from(target)
+                .map_err(|_| LoadError::new(LoadErrorKind::InvalidLabelTarget(label.clone())).at_offset(start))?;
+            label_targets.push((label.clone(), target, start));
+            program.labels.insert(label, label_target);
+        }
+        let start = reader.offset;
+        let instruction_count = reader.u32()?;
+        if instruction_count == 0 {
+            return Err(LoadError::new(LoadErrorKind::EmptyProgram));
+        }
+        if instruction_count as usize > MAX_INSTRUCTIONS {
+            return Err(LoadError::new(LoadErrorKind::TooManyInstructions(instruction_count)).at_offset(start));
+        }
+        for _ in 0..instruction_count {
+            let start = reader.offset;
+            let opcode = reader.u8()?;
+            let name = *OPCODES
+                .get(opcode as usize)
+                .ok_or_else(|| LoadError::new(LoadErrorKind::UnknownOpcode(opcode)).at_offset(start))?;
+            let mut instruction = Instruction {
+                name,
+                arg: None,
+                variable: None,
+                target: None,
+            };
+            match operand(name) {
+                Operand::None => {}
+                Operand::Value => instruction.arg = Some(reader.entry(&constants)?),
+                Operand::Name => instruction.variable = Some(reader.entry(&names)?),
+            }
+            match (instruction.label(), &instruction.variable) {
+                (None, Some(name)) if !lexer::is_identifier(name) => {
+                    return Err(LoadError::new(LoadErrorKind::InvalidName(name.clone())).at_offset(start + 1))
+                }
+                _ => {}
+            }
+            if let Some(label) = instruction.label() {
+                match program.labels.get(label) {
+                    Some(target) => instruction.target = Some(*target),
+                    None => {
+                        return Err(LoadError::new(LoadErrorKind::UndefinedLabel(label.to_string())).at_offset(start))
+                    }
+                }
+            }
+            program.instructions.push(instruction);
+        }
+        for (label, target, start) in label_targets {
+            if target > instruction_count {
+                return Err(LoadError::new(LoadErrorKind::InvalidLabelTarget(label)).at_offset(start));
+            }
+        }
+        let start = reader.offset;
+        let position_count = reader.u32()?;
+        if position_count != 0 && position_count != instruction_count {
+            return Err(LoadError::new(LoadErrorKind::InvalidPositions(position_count)).at_offset(start));
+        }
+        for _ in 0..position_count {
+            program.positions.push(SourcePosition {
+                line: reader.u32()? as usize,
+                column: reader.u32()? as usize,
+            });
+        }
+        if reader.offset != bytes.len() {
+            return Err(reader.error(LoadErrorKind::TrailingBytes(bytes.len() - reader.offset)));
+        }
+        Ok(program)
+    }
+
+    /// Whether the bytes start with the magic number of the binary format.
+    pub fn is_binary(bytes: &[u8]) -> bool {
+        bytes.starts_with(&MAGIC)
+    }
+
+    pub fn write_binary_file(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_binary())
+    }
+
+    pub fn load_binary_file(path: &str) -> Result<Self, LoadError> {
+        match fs::read(path) {
+            Ok(bytes) => ByteCode::from_binary(&bytes),
+            Err(e) => Err(LoadError::new(LoadErrorKind::OpenFile(e.to_string()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+    use crate::interpreter::interpret;
+
+    fn parse(code: &str) -> ByteCode {
+        ByteCode::parse_instructions(&mut BufReader::new(code.as_bytes())).unwrap()
+    }
+
+    const PROGRAM: &str = "LOAD_VAL 3\nWRITE_VAR n\n.loop\nREAD_VAR n\nJUMP_IF_FALSE .end\nREAD_VAR n\nDUP\n\
+        SEND_CHANNEL out\nLOAD_VAL 1\nREAD_VAR n\nSUB\nWRITE_VAR n\nJUMP .loop\n.end\nLOAD_VAL 3\nRETURN_VALUE\n.unused";
+
+    #[test]
+    fn round_trip_resource_programs() {
+        for path in ["resources/simple_program.txt", "resources/program_with_nested_loops.txt"] {
+            let file = File::open(path).unwrap();
+            let bytecode = ByteCode::parse_instructions(&mut BufReader::new(file)).unwrap();
+            let loaded = ByteCode::from_binary(&bytecode.to_binary()).unwrap();
+            assert_eq!(loaded, bytecode);
+            assert_eq!(interpret(loaded).unwrap(), interpret(bytecode).unwrap());
+        }
+    }
+
+    #[test]
+    fn round_trip_labels_names_and_constants() {
+        let bytecode = parse(PROGRAM);
+        let bytes = bytecode.to_binary();
+        assert!(ByteCode::is_binary(&bytes));
+        assert_eq!(bytes, bytecode.clone().to_binary());
+        assert_eq!(ByteCode::from_binary(&bytes), Ok(bytecode));
+    }
+
+    #[test]
+    fn round_trip_long_names() {
+        let variable = "v".repeat(70000);
+        let label = format!(".{}", "l".repeat(u16::MAX as usize));
+        let bytecode = parse(&format!("{label}\nLOAD_VAL 1\nWRITE_VAR {variable}\nREAD_VAR {variable}\nRETURN_VALUE"));
+        assert_eq!(ByteCode::from_binary(&bytecode.to_binary()), Ok(bytecode));
+    }
+
+    #[test]
+    fn reject_invalid_header() {
+        let bytes = parse(PROGRAM).to_binary();
+        assert_eq!(ByteCode::from_binary(b"LOAD_VAL 1"), Err(LoadError::new(LoadErrorKind::InvalidMagic)));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 9;
+        assert_eq!(
+            ByteCode::from_binary(&wrong_version),
+            Err(LoadError::new(LoadErrorKind::UnsupportedVersion(9)))
+        );
+
+        let mut wrong_width = bytes.clone();
+        wrong_width[6] = 3;
+        assert_eq!(
+            ByteCode::from_binary(&wrong_width),
+            Err(LoadError::new(LoadErrorKind::ValueTypeMismatch { width: 3, signed: SIGNED_VALUES }))
+        );
+
+        assert_eq!(
+            ByteCode::from_binary(&bytes[..bytes.len() - 1]),
+            Err(LoadError::new(LoadErrorKind::UnexpectedEnd).at_offset(HEADER_LENGTH))
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            ByteCode::from_binary(&trailing),
+            Err(LoadError::new(LoadErrorKind::TrailingBytes(1)).at_offset(bytes.len()))
+        );
+    }
+
+    #[test]
+    fn reject_corrupted_payload() {
+        let mut bytes = parse(PROGRAM).to_binary();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(matches!(
+            ByteCode::from_binary(&bytes),
+            Err(LoadError { offset: None, kind: LoadErrorKind::ChecksumMismatch { .. } })
+        ));
+    }
+
+    #[test]
+    fn reject_invalid_payload() {
+        let with_payload = |payload: &[u8]| {
+            let mut bytes = MAGIC.to_vec();
+            bytes.extend(FORMAT_VERSION.to_le_bytes());
+            bytes.extend([VALUE_WIDTH, SIGNED_VALUES as u8]);
+            bytes.extend((payload.len() as u32).to_le_bytes());
+            bytes.extend(checksum(payload).to_le_bytes());
+            bytes.extend(payload);
+            bytes
+        };
+        let no_tables = [0u8; 8];
+
+        let mut payload = no_tables.to_vec();
+        payload.extend([0, 0, 0, 0, 1, 0, 0, 0, 99]);
+        assert_eq!(
+            ByteCode::from_binary(&with_payload(&payload)),
+            Err(LoadError::new(LoadErrorKind::UnknownOpcode(99)).at_offset(HEADER_LENGTH + 16))
+        );
+
+        let mut payload = no_tables.to_vec();
+        payload.extend([0, 0, 0, 0, 1, 0, 0, 0, 0, 5, 0, 0, 0]);
+        assert_eq!(
+            ByteCode::from_binary(&with_payload(&payload)),
+            Err(LoadError::new(LoadErrorKind::InvalidIndex(5)).at_offset(HEADER_LENGTH + 17))
+        );
+
+        let mut payload = vec![1, 0, 0, 0, 2, 0, 0, 0];
+        payload.extend(b".l");
+        payload.extend([0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 22, 0, 0, 0, 0]);
+        assert_eq!(
+            ByteCode::from_binary(&with_payload(&payload)),
+            Err(LoadError::new(LoadErrorKind::UndefinedLabel(".l".to_string())).at_offset(HEADER_LENGTH + 22))
+        );
+
+        let mut payload = no_tables.to_vec();
+        payload.extend([0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            ByteCode::from_binary(&with_payload(&payload)),
+            Err(LoadError::new(LoadErrorKind::EmptyProgram))
+        );
+
+        let mut payload = vec![1, 0, 0, 0, 2, 0, 0, 0];
+        payload.extend(b".l");
+        payload.extend([0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        payload.extend(70000u32.to_le_bytes());
+        assert_eq!(
+            ByteCode::from_binary(&with_payload(&payload)),
+            Err(LoadError::new(LoadErrorKind::InvalidLabelTarget(".l".to_string())).at_offset(HEADER_LENGTH + 18))
+        );
+
+        let write = OPCODES.iter().position(|name| *name == InstructionName::WRITE).unwrap() as u8;
+        let mut payload = vec![1, 0, 0, 0, 2, 0, 0, 0];
+        payload.extend(b"a-");
+        payload.extend([0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, write, 0, 0, 0, 0]);
+        assert_eq!(
+            ByteCode::from_binary(&with_payload(&payload)),
+            Err(LoadError::new(LoadErrorKind::InvalidName("a-".to_string())).at_offset(HEADER_LENGTH + 23))
+        );
+
+        let mut payload = vec![1, 0, 0, 0, 2, 0, 0, 0];
+        payload.extend(b".l");
+        payload.extend([0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            ByteCode::from_binary(&with_payload(&payload)),
+            Err(LoadError::new(LoadErrorKind::DuplicatedLabel(".l".to_string())).at_offset(HEADER_LENGTH + 26))
+        );
+
+        let mut payload = no_tables.to_vec();
+        payload.extend([0, 0, 0, 0]);
+        payload.extend(70000u32.to_le_bytes());
+        assert_eq!(
+            ByteCode::from_binary(&with_payload(&payload)),
+            Err(LoadError::new(LoadErrorKind::TooManyInstructions(70000)).at_offset(HEADER_LENGTH + 12))
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt::Write;
+use super::{ByteCode, CommentPlacement};
+
+/// Indentation of instructions, labels are flush-left.
+pub(super) const INDENT: &str = "    ";
+/// Width of the instruction column of annotated output.
+const INSTRUCTION_WIDTH: usize = 24;
+
+/// What `disassemble` adds after every instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DisassemblyOptions {
+    /// Number of the instruction as in runtime errors, counting from 1.
+    pub instruction_indices: bool,
+    /// Line of the instruction in the source file, if the program was parsed from text.
+    pub line_numbers: bool,
+}
+
+/// Prints the program in the text syntax: every label on its own line before the instruction it points to,
+/// labels of the same instruction sorted by name, and every instruction indented on its own line.
+/// Comments of the source are written back before the labels of their instruction or after their label or
+/// instruction, placed like `format_source` does. Annotations are added after the instructions as comments,
+/// e.g. `; #3, line 5`. Parsing the output gives a program equal to `bytecode`.
+pub fn disassemble(bytecode: &ByteCode, options: DisassemblyOptions) -> String {
+    let mut labels: Vec<(&String, usize)> =
+        bytecode.labels().iter().map(|(label, target)| (label, *target as usize)).collect();
+    labels.sort_by(|(label1, target1), (label2, target2)| target1.cmp(target2).then(label1.cmp(label2)));
+    let mut labels = labels.into_iter().peekable();
+    let instruction_count = bytecode.instructions().len();
+    let mut text = String::new();
+    for index in 0..=instruction_count {
+        let labelled = labels.peek().is_some_and(|(_, target)| *target <= index);
+        for comment in bytecode.comments() {
+            if comment.instruction_index == index && comment.placement == CommentPlacement::OwnLine {
+                let indent = if index == 0 || index == instruction_count || labelled { "" } else { INDENT };
+                let _ = writeln!(text, "{}{}", indent, comment.text);
+            }
+        }
+        while let Some((label, _)) = labels.next_if(|(_, target)| *target <= index) {
+            let _ = write!(text, "{}", label);
+            for comment in bytecode.comments() {
+                if comment.placement == CommentPlacement::AfterLabel(label.clone()) {
+                    let _ = write!(text, " {}", comment.text);
+                }
+            }
+            text.push('\n');
+        }
+        let instruction = match bytecode.get_instruction(index) {
+            Some(instruction) => instruction,
+            None => break,
+        };
+        let comment = bytecode
+            .comments()
+            .iter()
+            .find(|comment| comment.instruction_index == index && comment.placement == CommentPlacement::AfterInstruction)
+            .map(|comment| comment.text.as_str());
+        let mut annotations = Vec::new();
+        if options.instruction_indices {
+            annotations.push(format!("#{}", index + 1));
+        }
+        if let Some(position) = bytecode.get_position(index).filter(|_| options.line_numbers) {
+            annotations.push(format!("line {}", position.line));
+        }
+        let _ = write!(text, "{}", INDENT);
+        if annotations.is_empty() {
+            let _ = write!(text, "{}", instruction);
+        } else {
+            let instruction = instruction.to_string();
+            let _ = write!(text, "{:width$} ; {}", instruction, annotations.join(", "), width = INSTRUCTION_WIDTH);
+        }
+        if let Some(comment) = comment {
+            let _ = write!(text, " {}", comment);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+    use crate::bytecode::optimizer::optimize;
+
+    fn parse(code: &str) -> ByteCode {
+        ByteCode::parse_instructions(&mut BufReader::new(code.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn disassemble_resource_programs() {
+        for path in ["resources/simple_program.txt", "resources/program_with_nested_loops.txt"] {
+            let file = File::open(path).unwrap();
+            let bytecode = ByteCode::parse_instructions(&mut BufReader::new(file)).unwrap();
+            let text = disassemble(&bytecode, DisassemblyOptions::default());
+            assert_eq!(parse(&text), bytecode);
+        }
+    }
+
+    #[test]
+    fn disassemble_labels() {
+        let bytecode = parse(".start\nLOAD_VAL 1\n.b\n.a\nCALL .f\nRETURN_VALUE\n  .f\nRET\n.end");
+        let text = disassemble(&bytecode, DisassemblyOptions::default());
+        assert_eq!(
+            text,
+            ".start\n    LOAD_VAL 1\n.a\n.b\n    CALL .f\n    RETURN_VALUE\n.f\n    RET\n.end\n"
+        );
+        assert_eq!(parse(&text), bytecode);
+    }
+
+    #[test]
+    fn disassemble_optimized_and_loaded_programs() {
+        let bytecode = parse("LOAD_VAL 2\nLOAD_VAL 3\nADD\nJUMP .end\nLOAD_VAL 1\n.end\nSEND_CHANNEL out\nLOAD_VAL 0\nRETURN_VALUE");
+        let optimized = optimize(&bytecode);
+        assert_eq!(parse(&disassemble(&optimized, DisassemblyOptions::default())), optimized);
+        let loaded = ByteCode::from_binary(&bytecode.to_binary()).unwrap();
+        assert_eq!(parse(&disassemble(&loaded, DisassemblyOptions::default())), bytecode);
+    }
+
+    #[test]
+    fn disassemble_comments() {
+        let code = "# counts down\nLOAD_VAL 3 ; start\n\n; before the loop\n.loop # body\n    ; decrement\n\
+            GOTO .loop ;again\n.end\n; done";
+        let bytecode = parse(code);
+        let text = disassemble(&bytecode, DisassemblyOptions::default());
+        assert_eq!(
+            text,
+            "# counts down\n    LOAD_VAL 3 ; start\n; before the loop\n; decrement\n.loop # body\n\
+            \x20   GOTO .loop ;again\n; done\n.end\n"
+        );
+        let parsed = parse(&text);
+        assert_eq!(parsed, bytecode);
+        assert_eq!(disassemble(&parsed, DisassemblyOptions::default()), text);
+    }
+
+    #[test]
+    fn disassemble_with_annotations() {
+        let bytecode = parse("LOAD_VAL 1\n\n.end\nRETURN_VALUE");
+        let options = DisassemblyOptions {
+            instruction_indices: true,
+            line_numbers: true,
+        };
+        assert_eq!(
+            disassemble(&bytecode, options),
+            "    LOAD_VAL 1               ; #1, line 1\n.end\n    RETURN_VALUE             ; #2, line 4\n"
+        );
+        assert_eq!(parse(&disassemble(&bytecode, options)), bytecode);
+        let options = DisassemblyOptions {
+            instruction_indices: false,
+            line_numbers: true,
+        };
+        assert_eq!(
+            disassemble(&bytecode, options),
+            "    LOAD_VAL 1               ; line 1\n.end\n    RETURN_VALUE             ; line 4\n"
+        );
+    }
+}
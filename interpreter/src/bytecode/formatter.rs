@@ -0,0 +1,169 @@
+use std::fs;
+use super::disassembler::INDENT;
+use super::lexer::{self, TokenKind};
+use super::{parse_statement, Statement};
+use crate::error::{ParseError, ParseErrorKind, ParseErrors};
+
+enum Line {
+    Blank,
+    Comment(String),
+    Label(String),
+    Instruction(String),
+}
+
+/// Rewrites the source of a program into the canonical form: labels flush-left, instructions indented by four
+/// spaces with uppercase mnemonics and a single space between operands, no runs of blank lines and a newline
+/// at the end. Comments are kept, a comment after code is separated from it by one space, a comment on its own
+/// line is indented like the next instruction, but flush-left before a label and at the start and the end of the file.
+/// Every line is checked with the parser, so a source with invalid lines isn't formatted and all of its errors
+/// are returned instead. Labels are not resolved, jumps to undefined labels are formatted as they are.
+pub fn format_source(source: &str) -> Result<String, ParseErrors> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line = uppercase_mnemonic(line);
+        let tokens = lexer::tokenize(&line);
+        let comment = tokens.last().filter(|token| token.kind == TokenKind::Comment).map(|token| token.text);
+        let code = match parse_statement(&tokens) {
+            Ok(None) => match comment {
+                Some(comment) => Line::Comment(comment.to_string()),
+                None => Line::Blank,
+            },
+            Ok(Some(Statement::Label(label))) => Line::Label(label),
+            Ok(Some(Statement::Instruction(instruction))) => Line::Instruction(instruction.to_string()),
+            Err(e) => {
+                errors.push(e.at_line(index + 1));
+                continue;
+            }
+        };
+        let formatted = match (code, comment) {
+            (Line::Label(code), Some(comment)) => Line::Label(format!("{} {}", code, comment)),
+            (Line::Instruction(code), Some(comment)) => Line::Instruction(format!("{} {}", code, comment)),
+            (code, _) => code,
+        };
+        if !matches!(formatted, Line::Blank) || lines.last().is_some_and(|last| !matches!(last, Line::Blank)) {
+            lines.push(formatted);
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ParseErrors(errors));
+    }
+    while lines.last().is_some_and(|last| matches!(last, Line::Blank)) {
+        lines.pop();
+    }
+    let mut formatted = String::new();
+    let mut code_seen = false;
+    for (index, line) in lines.iter().enumerate() {
+        match line {
+            Line::Blank => {}
+            Line::Comment(comment) => {
+                let next_code = lines[index + 1..]
+                    .iter()
+                    .find(|line| matches!(line, Line::Label(_) | Line::Instruction(_)));
+                if matches!(next_code, Some(Line::Instruction(_))) && code_seen {
+                    formatted.push_str(INDENT);
+                }
+                formatted.push_str(comment);
+            }
+            Line::Label(label) => {
+                formatted.push_str(label);
+                code_seen = true;
+            }
+            Line::Instruction(instruction) => {
+                formatted.push_str(INDENT);
+                code_seen = true;
+                formatted.push_str(instruction);
+            }
+        }
+        formatted.push('\n');
+    }
+    if formatted.is_empty() {
+        formatted.push('\n');
+    }
+    Ok(formatted)
+}
+
+/// Uppercases the mnemonic, the first token of the line if it is an identifier. The line keeps its length,
+/// so errors point to the same columns as in the source.
+fn uppercase_mnemonic(line: &str) -> String {
+    let mut line = line.to_string();
+    let span = lexer::tokenize(&line)
+        .first()
+        .filter(|token| token.kind == TokenKind::Identifier)
+        .map(|token| token.span);
+    if let Some(span) = span {
+        line[span.start..span.end].make_ascii_uppercase();
+    }
+    line
+}
+
+/// Whether `format_source` leaves the source unchanged.
+pub fn is_formatted(source: &str) -> Result<bool, ParseErrors> {
+    Ok(format_source(source)? == source)
+}
+
+pub fn format_file(source_file: &str) -> Result<String, ParseErrors> {
+    match fs::read_to_string(source_file) {
+        Ok(source) => format_source(&source),
+        Err(e) => Err(ParseError::new(ParseErrorKind::OpenFile(e.to_string())).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use crate::bytecode::ByteCode;
+    use crate::config::InstructionName;
+
+    fn parse(code: &str) -> ByteCode {
+        ByteCode::parse_instructions(&mut BufReader::new(code.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn format_resource_programs() {
+        for path in ["resources/simple_program.txt", "resources/program_with_nested_loops.txt"] {
+            let source = fs::read_to_string(path).unwrap();
+            let formatted = format_file(path).unwrap();
+            assert_eq!(parse(&formatted), parse(&source));
+            assert_eq!(is_formatted(&formatted), Ok(true));
+        }
+    }
+
+    #[test]
+    fn format_indentation_and_spacing() {
+        let source = "\n\nload_val   1\n\n\n   .loop\nDup\n\t  WRITE_VAR  x\nRead_Var x\n  JUMP .loop\n\n";
+        assert_eq!(
+            format_source(source),
+            Ok("    LOAD_VAL 1\n\n.loop\n    DUP\n    WRITE_VAR x\n    READ_VAR x\n    JUMP .loop\n".to_string())
+        );
+        assert_eq!(is_formatted(source), Ok(false));
+        assert_eq!(is_formatted("    LOAD_VAL 1\n    RETURN_VALUE"), Ok(false));
+        assert_eq!(is_formatted("    LOAD_VAL 1\n    RETURN_VALUE\n"), Ok(true));
+    }
+
+    #[test]
+    fn report_every_invalid_line() {
+        let errors = format_source("LOAD_VAL\nADD\nPUSH 1\nJUMP .undefined").unwrap_err();
+        assert_eq!(
+            errors,
+            ParseErrors(vec![
+                ParseError::for_instruction(InstructionName::LOAD, ParseErrorKind::ArgumentCount { expected: 1, got: 0 })
+                    .at_line(1)
+                    .at_column(9),
+                ParseError::new(ParseErrorKind::UnknownInstruction("PUSH".to_string())).at_line(3).at_column(1),
+            ])
+        );
+    }
+
+    #[test]
+    fn format_comments() {
+        let source = "# counts down\n# from 3\nload_val 3   ;start\n;\n .loop   # body  \n  ; decrement\n\n\
+            GOTO .loop ; again\n\n; done";
+        let formatted = "# counts down\n# from 3\n    LOAD_VAL 3 ;start\n;\n.loop # body\n    ; decrement\n\n\
+            \x20   GOTO .loop ; again\n\n; done\n";
+        assert_eq!(format_source(source), Ok(formatted.to_string()));
+        assert_eq!(is_formatted(formatted), Ok(true));
+        assert_eq!(parse(formatted).comments().len(), 8);
+    }
+}
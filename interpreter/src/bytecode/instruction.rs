@@ -1,227 +1,635 @@
-use crate::config::{InstructionName, VariableValue};
-use lazy_static::lazy_static;
-use regex::Regex;
-use crate::ByteCode;
+use std::fmt;
+use crate::config::{InstructionName, MaxInstructionNumber, VariableValue, SIGNED_VALUES};
+use crate::error::{ParseError, ParseErrorKind};
+use super::lexer::{Token, TokenKind};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Instruction {
     pub name: InstructionName,
     pub arg: Option<VariableValue>,
     pub variable: Option<String>,
+    /// Index of the instruction the label operand points to, resolved by `ByteCode` after parsing.
+    pub target: Option<MaxInstructionNumber>,
 }
 
 impl Instruction {
+    /// Returns the label operand of jumps, `SPAWN` and `CALL`.
+    pub fn label(&self) -> Option<&str> {
+        match self.name {
+            InstructionName::GOTO
+            | InstructionName::JUMP
+            | InstructionName::JUMPIF
+            | InstructionName::JUMPIFNOT
+            | InstructionName::SPAWN
+            | InstructionName::CALL => self.variable.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Creates the instruction from the mnemonic and operand tokens of a source line. The kind of every operand
+    /// is checked, errors point to the column of the offending token or after the last token if one is missing.
+    pub fn new(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError>{
+        match mnemonic.text {
+            "LOAD_VAL" => Instruction::create_load_instruction(mnemonic, operands),
+            "WRITE_VAR" => Instruction::create_write_instruction(mnemonic, operands),
+            "READ_VAR" => Instruction::create_read_instruction(mnemonic, operands),
+            "ADD" => Instruction::create_add_instruction(mnemonic, operands),
+            "SUB" => Instruction::create_sub_instruction(mnemonic, operands),
+            "MULTIPLY" => Instruction::create_multiply_instruction(mnemonic, operands),
+            "DIV" => Instruction::create_div_instruction(mnemonic, operands),
+            "MOD" => Instruction::create_mod_instruction(mnemonic, operands),
+            "NEG" => Instruction::create_neg_instruction(mnemonic, operands),
+            "AND" => Instruction::create_and_instruction(mnemonic, operands),
+            "OR" => Instruction::create_or_instruction(mnemonic, operands),
+            "XOR" => Instruction::create_xor_instruction(mnemonic, operands),
+            "NOT" => Instruction::create_not_instruction(mnemonic, operands),
+            "SHL" => Instruction::create_shl_instruction(mnemonic, operands),
+            "SHR" => Instruction::create_shr_instruction(mnemonic, operands),
+            "RETURN_VALUE" => Instruction::create_return_instruction(mnemonic, operands),
+            "GREATER" => Instruction::create_greater_instruction(mnemonic, operands),
+            "LESS" => Instruction::create_less_instruction(mnemonic, operands),
+            "EQUAL" => Instruction::create_equal_instruction(mnemonic, operands),
+            "DUP" => Instruction::create_dup_instruction(mnemonic, operands),
+            "POP" => Instruction::create_pop_instruction(mnemonic, operands),
+            "GOTO" => Instruction::create_goto_instruction(mnemonic, operands),
+            "JUMP" => Instruction::create_jump_instruction(mnemonic, operands),
+            "JUMP_IF_TRUE" => Instruction::create_jump_if_true_instruction(mnemonic, operands),
+            "JUMP_IF_FALSE" => Instruction::create_jump_if_false_instruction(mnemonic, operands),
+            "SPAWN" => Instruction::create_spawn_instruction(mnemonic, operands),
+            "SEND_CHANNEL" => Instruction::create_send_instruction(mnemonic, operands),
+            "RECV_CHANNEL" => Instruction::create_recv_instruction(mnemonic, operands),
+            "CALL" => Instruction::create_call_instruction(mnemonic, operands),
+            "RET" => Instruction::create_ret_instruction(mnemonic, operands),
+            _other => {
+                let kind = ParseErrorKind::UnknownInstruction(mnemonic.text.to_string());
+                Err(ParseError::new(kind).at_column(mnemonic.column))
+            }
+        }
+    }
 
-    pub fn new(instruction_name: &str, args: Vec<String>) -> Result<Self, String>{
-        let instruction = match instruction_name {
-            "LOAD_VAL" => Instruction::create_load_instruction(args),
-            "WRITE_VAR" => Instruction::create_write_instruction(args),
-            "READ_VAR" => Instruction::create_read_instruction(args),
-            "ADD" => Instruction::create_add_instruction(args),
-            "MULTIPLY" => Instruction::create_multiply_instruction(args),
-            "RETURN_VALUE" => Instruction::create_return_instruction(args),
-            "GREATER" => Instruction::create_greater_instruction(args),
-            "LESS" => Instruction::create_less_instruction(args),
-            "EQUAL" => Instruction::create_equal_instruction(args),
-            "DUP" => Instruction::create_dup_instruction(args),
-            "POP" => Instruction::create_pop_instruction(args),
-            "GOTO" => Instruction::create_goto_instruction(args),
-            _other => Err(format!("Unknown instruction: {}", instruction_name)),
+    /// Error for a wrong number of operands, at the first extra operand or after the last token.
+    fn argument_count_error(
+        name: InstructionName,
+        expected: usize,
+        mnemonic: &Token,
+        operands: &[Token],
+    ) -> ParseError {
+        let column = match operands.get(expected) {
+            Some(extra) => extra.column,
+            None => operands.last().unwrap_or(mnemonic).end_column(),
         };
-        return instruction;
+        let kind = ParseErrorKind::ArgumentCount { expected, got: operands.len() };
+        ParseError::for_instruction(name, kind).at_column(column)
+    }
+
+    /// Text of an operand of the given kind, otherwise the error made by `error` at the operand.
+    fn operand(
+        name: InstructionName,
+        operand: &Token,
+        kind: TokenKind,
+        error: fn(String) -> ParseErrorKind,
+    ) -> Result<String, ParseError> {
+        if operand.kind != kind {
+            let kind = error(operand.text.to_string());
+            return Err(ParseError::for_instruction(name, kind).at_column(operand.column));
+        }
+        Ok(operand.text.to_string())
     }
 
-    fn create_load_instruction(args: Vec<String>) -> Result<Self, String> {
-        if args.len() != 1 {
-            return Err(format!("Error creating load instruction: expected 1 argument, got {}", args.len()))
+    fn create_load_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::LOAD, 1, mnemonic, operands))
         }
 
-        let arg = match args.get(0).unwrap().parse::<VariableValue>() {
+        let literal = Instruction::operand(
+            InstructionName::LOAD,
+            &operands[0],
+            TokenKind::Number,
+            ParseErrorKind::InvalidNumber,
+        )?;
+        let column = operands[0].column;
+        if !SIGNED_VALUES && literal.starts_with('-') {
+            let kind = ParseErrorKind::NegativeValue(literal);
+            return Err(ParseError::for_instruction(InstructionName::LOAD, kind).at_column(column));
+        }
+        let arg = match literal.parse::<VariableValue>() {
             Ok(v) => v,
-            Err(e) => return Err(format!("Error creating load instruction: {}", e)),
+            Err(e) => {
+                let kind = ParseErrorKind::InvalidValue(e);
+                return Err(ParseError::for_instruction(InstructionName::LOAD, kind).at_column(column));
+            }
         };
 
         let instr = Instruction {
             name: InstructionName::LOAD,
             arg: Some(arg),
             variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_write_instruction(args: Vec<String>) -> Result<Self, String> {
-        if args.len() != 1 {
-            return Err(format!("Error creating write instruction: expected 1 argument, got {}", args.len()))
+    fn create_write_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::WRITE, 1, mnemonic, operands))
         }
 
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*").expect("Invalid regex");
-        }
-        let arg = args.get(0).unwrap().to_string();
-        if !RE.is_match(&arg) {
-            return Err(format!("Invalid variable name {}", arg));
-        }
+        let arg = Instruction::operand(
+            InstructionName::WRITE,
+            &operands[0],
+            TokenKind::Identifier,
+            ParseErrorKind::InvalidVariableName,
+        )?;
 
         let instr = Instruction {
             name: InstructionName::WRITE,
             arg: None,
             variable: Some(arg),
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_read_instruction(args: Vec<String>) -> Result<Self, String> {
-        if args.len() != 1 {
-            return Err(format!("Error creating read instruction: expected 1 argument, got {}", args.len()))
-        }
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*").expect("Invalid regex");
-        }
-        let arg = args.get(0).unwrap().to_string();
-        if !RE.is_match(&arg) {
-            return Err(format!("Invalid variable name {}", arg));
+    fn create_read_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::READ, 1, mnemonic, operands))
         }
+        let arg = Instruction::operand(
+            InstructionName::READ,
+            &operands[0],
+            TokenKind::Identifier,
+            ParseErrorKind::InvalidVariableName,
+        )?;
 
         let instr = Instruction {
             name: InstructionName::READ,
             arg: None,
             variable: Some(arg),
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_add_instruction(args: Vec<String>) -> Result<Self, String> {
-        if !args.is_empty() {
-            return Err(format!("Error creating add instruction: expected 0 argument, got {}", args.len()))
+    fn create_add_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::ADD, 0, mnemonic, operands))
         }
 
         let instr = Instruction {
             name: InstructionName::ADD,
             arg: None,
             variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_multiply_instruction(args: Vec<String>) -> Result<Self, String> {
-        if !args.is_empty() {
-            return Err(format!("Error creating multiply instruction: expected 0 argument, got {}", args.len()))
+    fn create_multiply_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::MULTIPLY, 0, mnemonic, operands))
         }
 
         let instr = Instruction {
             name: InstructionName::MULTIPLY,
             arg: None,
             variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_sub_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::SUB, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::SUB,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_div_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::DIV, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::DIV,
+            arg: None,
+            variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_return_instruction(args: Vec<String>) -> Result<Self, String> {
-        if !args.is_empty() {
-            return Err(format!("Error creating return instruction: expected 0 argument, got {}", args.len()))
+    fn create_mod_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::MOD, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::MOD,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_neg_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::NEG, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::NEG,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_and_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::AND, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::AND,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_or_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::OR, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::OR,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_xor_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::XOR, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::XOR,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_not_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::NOT, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::NOT,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_shl_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::SHL, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::SHL,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_shr_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::SHR, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::SHR,
+            arg: None,
+            variable: None,
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_return_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::RETURN, 0, mnemonic, operands))
         }
 
         let instr = Instruction {
             name: InstructionName::RETURN,
             arg: None,
             variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_greater_instruction(args: Vec<String>) -> Result<Self, String> {
-        if !args.is_empty() {
-            return Err(format!("Error creating greater instruction: expected 0 argument, got {}", args.len()))
+    fn create_greater_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::GREATER, 0, mnemonic, operands))
         }
 
         let instr = Instruction {
             name: InstructionName::GREATER,
             arg: None,
             variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_less_instruction(args: Vec<String>) -> Result<Self, String> {
-        if !args.is_empty() {
-            return Err(format!("Error creating less instruction: expected 0 argument, got {}", args.len()))
+    fn create_less_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::LESS, 0, mnemonic, operands))
         }
 
         let instr = Instruction {
             name: InstructionName::LESS,
             arg: None,
             variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_equal_instruction(args: Vec<String>) -> Result<Self, String> {
-        if !args.is_empty() {
-            return Err(format!("Error creating equal instruction: expected 0 argument, got {}", args.len()))
+    fn create_equal_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::EQUAL, 0, mnemonic, operands))
         }
 
         let instr = Instruction {
             name: InstructionName::EQUAL,
             arg: None,
             variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_dup_instruction(args: Vec<String>) -> Result<Self, String> {
-        if !args.is_empty() {
-            return Err(format!("Error creating dup instruction: expected 0 argument, got {}", args.len()))
+    fn create_dup_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::DUP, 0, mnemonic, operands))
         }
 
         let instr = Instruction {
             name: InstructionName::DUP,
             arg: None,
             variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_pop_instruction(args: Vec<String>) -> Result<Self, String> {
-        if !args.is_empty() {
-            return Err(format!("Error creating pop instruction: expected 0 argument, got {}", args.len()))
+    fn create_pop_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::POP, 0, mnemonic, operands))
         }
 
         let instr = Instruction {
             name: InstructionName::POP,
             arg: None,
             variable: None,
+            target: None,
         };
         Ok(instr)
     }
 
-    fn create_goto_instruction(args: Vec<String>) -> Result<Self, String> {
-        if args.len() != 1 {
-            return Err(format!("Error creating goto instruction: expected 1 argument, got {}", args.len()))
-        }
-        let arg = args.get(0).unwrap().to_string();
-        if !ByteCode::is_label(&arg) {
-            return Err(format!("Invalid label name: {}", arg));
+    fn create_goto_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::GOTO, 1, mnemonic, operands))
         }
+        let arg = Instruction::operand(
+            InstructionName::GOTO,
+            &operands[0],
+            TokenKind::Label,
+            ParseErrorKind::InvalidLabelName,
+        )?;
 
         let instr = Instruction {
             name: InstructionName::GOTO,
             arg: None,
             variable: Some(arg),
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_jump_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::JUMP, 1, mnemonic, operands))
+        }
+        let arg = Instruction::operand(
+            InstructionName::JUMP,
+            &operands[0],
+            TokenKind::Label,
+            ParseErrorKind::InvalidLabelName,
+        )?;
+
+        let instr = Instruction {
+            name: InstructionName::JUMP,
+            arg: None,
+            variable: Some(arg),
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_jump_if_true_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::JUMPIF, 1, mnemonic, operands))
+        }
+        let arg = Instruction::operand(
+            InstructionName::JUMPIF,
+            &operands[0],
+            TokenKind::Label,
+            ParseErrorKind::InvalidLabelName,
+        )?;
+
+        let instr = Instruction {
+            name: InstructionName::JUMPIF,
+            arg: None,
+            variable: Some(arg),
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_jump_if_false_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::JUMPIFNOT, 1, mnemonic, operands))
+        }
+        let arg = Instruction::operand(
+            InstructionName::JUMPIFNOT,
+            &operands[0],
+            TokenKind::Label,
+            ParseErrorKind::InvalidLabelName,
+        )?;
+
+        let instr = Instruction {
+            name: InstructionName::JUMPIFNOT,
+            arg: None,
+            variable: Some(arg),
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_spawn_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::SPAWN, 1, mnemonic, operands))
+        }
+        let arg = Instruction::operand(
+            InstructionName::SPAWN,
+            &operands[0],
+            TokenKind::Label,
+            ParseErrorKind::InvalidLabelName,
+        )?;
+
+        let instr = Instruction {
+            name: InstructionName::SPAWN,
+            arg: None,
+            variable: Some(arg),
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_send_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::SEND, 1, mnemonic, operands))
+        }
+        let arg = Instruction::operand(
+            InstructionName::SEND,
+            &operands[0],
+            TokenKind::Identifier,
+            ParseErrorKind::InvalidChannelName,
+        )?;
+
+        let instr = Instruction {
+            name: InstructionName::SEND,
+            arg: None,
+            variable: Some(arg),
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_recv_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::RECV, 1, mnemonic, operands))
+        }
+        let arg = Instruction::operand(
+            InstructionName::RECV,
+            &operands[0],
+            TokenKind::Identifier,
+            ParseErrorKind::InvalidChannelName,
+        )?;
+
+        let instr = Instruction {
+            name: InstructionName::RECV,
+            arg: None,
+            variable: Some(arg),
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_call_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if operands.len() != 1 {
+            return Err(Instruction::argument_count_error(InstructionName::CALL, 1, mnemonic, operands))
+        }
+        let arg = Instruction::operand(
+            InstructionName::CALL,
+            &operands[0],
+            TokenKind::Label,
+            ParseErrorKind::InvalidLabelName,
+        )?;
+
+        let instr = Instruction {
+            name: InstructionName::CALL,
+            arg: None,
+            variable: Some(arg),
+            target: None,
+        };
+        Ok(instr)
+    }
+
+    fn create_ret_instruction(mnemonic: &Token, operands: &[Token]) -> Result<Self, ParseError> {
+        if !operands.is_empty() {
+            return Err(Instruction::argument_count_error(InstructionName::RET, 0, mnemonic, operands))
+        }
+
+        let instr = Instruction {
+            name: InstructionName::RET,
+            arg: None,
+            variable: None,
+            target: None,
         };
         Ok(instr)
     }
 }
 
+/// Formats the instruction as a line of a source file, e.g. `LOAD_VAL 5` or `GOTO .loop`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name.mnemonic())?;
+        if let Some(arg) = self.arg {
+            write!(f, " {}", arg)?;
+        }
+        if let Some(variable) = &self.variable {
+            write!(f, " {}", variable)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::vec_init_then_push)]
 mod tests {
     use super::*;
+    use crate::bytecode::lexer::tokenize;
+
+    /// Creates the instruction from a line with the mnemonic and the operands separated by single spaces.
+    fn new(instruction_name: &str, args: Vec<String>) -> Result<Instruction, ParseError> {
+        let line = std::iter::once(instruction_name.to_string()).chain(args).collect::<Vec<_>>().join(" ");
+        let tokens = tokenize(&line);
+        Instruction::new(&tokens[0], &tokens[1..])
+    }
 
     #[test]
     fn create_load_instruction() {
         let instruction_name = "LOAD_VAL";
         let mut args= Vec::new();
         args.push("1".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::LOAD);
@@ -235,10 +643,13 @@ mod tests {
         let mut args= Vec::new();
         args.push("1".to_string());
         args.push("2".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating load instruction: expected 1 argument, got 2".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::LOAD,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 2 },
+            ).at_column(12))
         );
     }
 
@@ -246,19 +657,43 @@ mod tests {
     fn create_load_instruction_with_zero_args() {
         let instruction_name = "LOAD_VAL";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating load instruction: expected 1 argument, got 0".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::LOAD,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(9))
         );
     }
 
+    #[test]
+    fn create_load_instruction_with_invalid_number() {
+        let instruction_name = "LOAD_VAL";
+        let mut args= Vec::new();
+        args.push("1x".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::LOAD,
+                ParseErrorKind::InvalidNumber("1x".to_string()),
+            ).at_column(10))
+        );
+
+        let mut args= Vec::new();
+        args.push("9".repeat(50));
+        let error = new(instruction_name, args).unwrap_err();
+        assert!(matches!(error.kind, ParseErrorKind::InvalidValue(_)));
+        assert_eq!(error.column, Some(10));
+    }
+
     #[test]
     fn create_write_instruction() {
         let instruction_name = "WRITE_VAR";
         let mut args= Vec::new();
         args.push("x".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::WRITE);
@@ -272,10 +707,13 @@ mod tests {
         let mut args= Vec::new();
         args.push("x".to_string());
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating write instruction: expected 1 argument, got 2".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::WRITE,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 2 },
+            ).at_column(13))
         );
     }
 
@@ -283,10 +721,13 @@ mod tests {
     fn create_write_instruction_with_zero_args() {
         let instruction_name = "WRITE_VAR";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating write instruction: expected 1 argument, got 0".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::WRITE,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(10))
         );
     }
 
@@ -295,7 +736,7 @@ mod tests {
         let instruction_name = "READ_VAR";
         let mut args= Vec::new();
         args.push("x".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::READ);
@@ -309,10 +750,13 @@ mod tests {
         let mut args= Vec::new();
         args.push("x".to_string());
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating read instruction: expected 1 argument, got 2".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::READ,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 2 },
+            ).at_column(12))
         );
     }
 
@@ -320,10 +764,13 @@ mod tests {
     fn create_read_instruction_with_zero_args() {
         let instruction_name = "READ_VAR";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating read instruction: expected 1 argument, got 0".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::READ,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(9))
         );
     }
 
@@ -331,7 +778,7 @@ mod tests {
     fn create_add_instruction() {
         let instruction_name = "ADD";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::ADD);
@@ -344,10 +791,13 @@ mod tests {
         let instruction_name = "ADD";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating add instruction: expected 0 argument, got 1".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::ADD,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
         );
     }
 
@@ -355,7 +805,7 @@ mod tests {
     fn create_multiply_instruction() {
         let instruction_name = "MULTIPLY";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::MULTIPLY);
@@ -368,10 +818,13 @@ mod tests {
         let instruction_name = "MULTIPLY";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating multiply instruction: expected 0 argument, got 1".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::MULTIPLY,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(10))
         );
     }
 
@@ -379,7 +832,7 @@ mod tests {
     fn create_return_instruction() {
         let instruction_name = "RETURN_VALUE";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::RETURN);
@@ -392,10 +845,13 @@ mod tests {
         let instruction_name = "RETURN_VALUE";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating return instruction: expected 0 argument, got 1".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::RETURN,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(14))
         );
     }
 
@@ -404,10 +860,10 @@ mod tests {
         let instruction_name = "INSTRUCTION_NAME";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Unknown instruction: INSTRUCTION_NAME".to_string())
+            Err(ParseError::new(ParseErrorKind::UnknownInstruction("INSTRUCTION_NAME".to_string())).at_column(1))
         );
     }
 
@@ -415,7 +871,7 @@ mod tests {
     fn create_greater_instruction() {
         let instruction_name = "GREATER";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::GREATER);
@@ -428,10 +884,13 @@ mod tests {
         let instruction_name = "GREATER";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating greater instruction: expected 0 argument, got 1".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::GREATER,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(9))
         );
     }
 
@@ -439,7 +898,7 @@ mod tests {
     fn create_less_instruction() {
         let instruction_name = "LESS";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::LESS);
@@ -452,10 +911,13 @@ mod tests {
         let instruction_name = "LESS";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating less instruction: expected 0 argument, got 1".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::LESS,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(6))
         );
     }
 
@@ -463,7 +925,7 @@ mod tests {
     fn create_equal_instruction() {
         let instruction_name = "EQUAL";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::EQUAL);
@@ -476,10 +938,13 @@ mod tests {
         let instruction_name = "EQUAL";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating equal instruction: expected 0 argument, got 1".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::EQUAL,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(7))
         );
     }
 
@@ -487,7 +952,7 @@ mod tests {
     fn create_dup_instruction() {
         let instruction_name = "DUP";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::DUP);
@@ -500,10 +965,13 @@ mod tests {
         let instruction_name = "DUP";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating dup instruction: expected 0 argument, got 1".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::DUP,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
         );
     }
 
@@ -511,7 +979,7 @@ mod tests {
     fn create_pop_instruction() {
         let instruction_name = "POP";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::POP);
@@ -524,10 +992,13 @@ mod tests {
         let instruction_name = "POP";
         let mut args= Vec::new();
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating pop instruction: expected 0 argument, got 1".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::POP,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
         );
     }
 
@@ -536,7 +1007,7 @@ mod tests {
         let instruction_name = "GOTO";
         let mut args= Vec::new();
         args.push(".label".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert!(result.is_ok());
         let instruction = result.ok().unwrap();
         assert_eq!(instruction.name, InstructionName::GOTO);
@@ -550,10 +1021,13 @@ mod tests {
         let mut args= Vec::new();
         args.push("x".to_string());
         args.push("y".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating goto instruction: expected 1 argument, got 2".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::GOTO,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 2 },
+            ).at_column(8))
         );
     }
 
@@ -561,10 +1035,13 @@ mod tests {
     fn create_goto_instruction_with_zero_args() {
         let instruction_name = "GOTO";
         let args= Vec::new();
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Error creating goto instruction: expected 1 argument, got 0".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::GOTO,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(5))
         );
     }
 
@@ -573,10 +1050,541 @@ mod tests {
         let instruction_name = "GOTO";
         let mut args= Vec::new();
         args.push("label.".to_string());
-        let result = Instruction::new(instruction_name, args);
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::GOTO,
+                ParseErrorKind::InvalidLabelName("label.".to_string()),
+            ).at_column(6))
+        );
+    }
+
+    #[test]
+    fn create_spawn_instruction() {
+        let instruction_name = "SPAWN";
+        let mut args= Vec::new();
+        args.push(".worker".to_string());
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::SPAWN);
+        assert_eq!(instruction.variable, Some(".worker".to_string()));
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_spawn_instruction_with_incorrect_label_name() {
+        let instruction_name = "SPAWN";
+        let mut args= Vec::new();
+        args.push("worker".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::SPAWN,
+                ParseErrorKind::InvalidLabelName("worker".to_string()),
+            ).at_column(7))
+        );
+    }
+
+    #[test]
+    fn create_send_instruction() {
+        let instruction_name = "SEND_CHANNEL";
+        let mut args= Vec::new();
+        args.push("ch".to_string());
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::SEND);
+        assert_eq!(instruction.variable, Some("ch".to_string()));
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_send_instruction_with_zero_args() {
+        let instruction_name = "SEND_CHANNEL";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::SEND,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(13))
+        );
+    }
+
+    #[test]
+    fn create_recv_instruction() {
+        let instruction_name = "RECV_CHANNEL";
+        let mut args= Vec::new();
+        args.push("ch".to_string());
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::RECV);
+        assert_eq!(instruction.variable, Some("ch".to_string()));
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_recv_instruction_with_incorrect_channel_name() {
+        let instruction_name = "RECV_CHANNEL";
+        let mut args= Vec::new();
+        args.push("0ch".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::RECV,
+                ParseErrorKind::InvalidChannelName("0ch".to_string()),
+            ).at_column(14))
+        );
+    }
+
+    #[test]
+    fn create_call_instruction() {
+        let instruction_name = "CALL";
+        let mut args= Vec::new();
+        args.push(".function".to_string());
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::CALL);
+        assert_eq!(instruction.variable, Some(".function".to_string()));
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_call_instruction_with_zero_args() {
+        let instruction_name = "CALL";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::CALL,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_ret_instruction() {
+        let instruction_name = "RET";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::RET);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_ret_instruction_with_more_than_zero_arg() {
+        let instruction_name = "RET";
+        let mut args= Vec::new();
+        args.push("x".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::RET,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_sub_instruction() {
+        let instruction_name = "SUB";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::SUB);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_sub_instruction_with_more_than_zero_arg() {
+        let instruction_name = "SUB";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::SUB,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_div_instruction() {
+        let instruction_name = "DIV";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::DIV);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_div_instruction_with_more_than_zero_arg() {
+        let instruction_name = "DIV";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::DIV,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_mod_instruction() {
+        let instruction_name = "MOD";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::MOD);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_mod_instruction_with_more_than_zero_arg() {
+        let instruction_name = "MOD";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::MOD,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_neg_instruction() {
+        let instruction_name = "NEG";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::NEG);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_neg_instruction_with_more_than_zero_arg() {
+        let instruction_name = "NEG";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::NEG,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_load_instruction_with_negative_value() {
+        let instruction_name = "LOAD_VAL";
+        let mut args= Vec::new();
+        args.push("-5".to_string());
+        let result = new(instruction_name, args);
+        if SIGNED_VALUES {
+            assert_eq!(result.ok().unwrap().arg, "-5".parse::<VariableValue>().ok());
+        } else {
+            assert_eq!(
+                result.map_err(|e| e.to_string()),
+                Err("Error creating load instruction: negative value -5 requires a signed value type".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn create_and_instruction() {
+        let instruction_name = "AND";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::AND);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_and_instruction_with_more_than_zero_arg() {
+        let instruction_name = "AND";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::AND,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_or_instruction() {
+        let instruction_name = "OR";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::OR);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_or_instruction_with_more_than_zero_arg() {
+        let instruction_name = "OR";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::OR,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(4))
+        );
+    }
+
+    #[test]
+    fn create_xor_instruction() {
+        let instruction_name = "XOR";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::XOR);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_xor_instruction_with_more_than_zero_arg() {
+        let instruction_name = "XOR";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::XOR,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_not_instruction() {
+        let instruction_name = "NOT";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::NOT);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_not_instruction_with_more_than_zero_arg() {
+        let instruction_name = "NOT";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::NOT,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_shl_instruction() {
+        let instruction_name = "SHL";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::SHL);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_shl_instruction_with_more_than_zero_arg() {
+        let instruction_name = "SHL";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::SHL,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_shr_instruction() {
+        let instruction_name = "SHR";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::SHR);
+        assert_eq!(instruction.variable, None);
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_shr_instruction_with_more_than_zero_arg() {
+        let instruction_name = "SHR";
+        let mut args= Vec::new();
+        args.push("y".to_string());
+        let result = new(instruction_name, args);
         assert_eq!(
             result,
-            Err("Invalid label name: label.".to_string())
+            Err(ParseError::for_instruction(
+                InstructionName::SHR,
+                ParseErrorKind::ArgumentCount { expected: 0, got: 1 },
+            ).at_column(5))
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn create_jump_instruction() {
+        let instruction_name = "JUMP";
+        let mut args= Vec::new();
+        args.push(".label".to_string());
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::JUMP);
+        assert_eq!(instruction.variable, Some(".label".to_string()));
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_jump_instruction_with_zero_args() {
+        let instruction_name = "JUMP";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::JUMP,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(5))
+        );
+    }
+
+    #[test]
+    fn create_jump_if_true_instruction() {
+        let instruction_name = "JUMP_IF_TRUE";
+        let mut args= Vec::new();
+        args.push(".label".to_string());
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::JUMPIF);
+        assert_eq!(instruction.variable, Some(".label".to_string()));
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_jump_if_true_instruction_with_zero_args() {
+        let instruction_name = "JUMP_IF_TRUE";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::JUMPIF,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(13))
+        );
+    }
+
+    #[test]
+    fn create_jump_if_false_instruction() {
+        let instruction_name = "JUMP_IF_FALSE";
+        let mut args= Vec::new();
+        args.push(".label".to_string());
+        let result = new(instruction_name, args);
+        assert!(result.is_ok());
+        let instruction = result.ok().unwrap();
+        assert_eq!(instruction.name, InstructionName::JUMPIFNOT);
+        assert_eq!(instruction.variable, Some(".label".to_string()));
+        assert_eq!(instruction.arg, None);
+    }
+
+    #[test]
+    fn create_jump_if_false_instruction_with_zero_args() {
+        let instruction_name = "JUMP_IF_FALSE";
+        let args= Vec::new();
+        let result = new(instruction_name, args);
+        assert_eq!(
+            result,
+            Err(ParseError::for_instruction(
+                InstructionName::JUMPIFNOT,
+                ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+            ).at_column(14))
+        );
+    }
+
+    #[test]
+    fn get_label_operand() {
+        let mut args= Vec::new();
+        args.push(".label".to_string());
+        let call = new("CALL", args).ok().unwrap();
+        assert_eq!(call.label(), Some(".label"));
+        assert_eq!(call.target, None);
+
+        let mut args= Vec::new();
+        args.push("x".to_string());
+        let read = new("READ_VAR", args).ok().unwrap();
+        assert_eq!(read.label(), None);
+    }
+
+    #[test]
+    fn display_instructions() {
+        for line in ["LOAD_VAL 5", "WRITE_VAR x", "ADD", "JUMP_IF_TRUE .loop", "SEND_CHANNEL ch", "RET"] {
+            let mut parts = line.split_whitespace().map(|s| s.to_string());
+            let name = parts.next().unwrap();
+            let instruction = new(&name, parts.collect()).ok().unwrap();
+            assert_eq!(instruction.to_string(), line);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::COMMENT_MARKERS;
+
+/// Byte range of a token in its source line, `end` is exclusive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenKind {
+    /// Letters, digits and underscores not starting with a digit: mnemonics, variable and channel names.
+    Identifier,
+    /// A dot followed by letters, digits and underscores.
+    Label,
+    /// Decimal digits with an optional sign.
+    Number,
+    /// From a comment marker to the end of the line, without trailing whitespace.
+    Comment,
+    /// Any other run of characters.
+    Invalid,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+    /// Column of the first character in the line, counting characters from 1.
+    pub column: usize,
+}
+
+impl Token<'_> {
+    /// Column right after the last character of the token.
+    pub fn end_column(&self) -> usize {
+        self.column + self.text.chars().count()
+    }
+}
+
+/// Splits a source line into tokens. A token is a run of characters up to whitespace or a comment marker,
+/// a comment is always the last token of its line.
+pub fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    let mut column = 0;
+    while let Some((start, c)) = chars.next() {
+        column += 1;
+        if c.is_whitespace() {
+            continue;
+        }
+        let token_column = column;
+        if COMMENT_MARKERS.contains(&c) {
+            let text = line[start..].trim_end();
+            tokens.push(Token {
+                kind: TokenKind::Comment,
+                text,
+                span: Span { start, end: start + text.len() },
+                column: token_column,
+            });
+            break;
+        }
+        let mut end = start + c.len_utf8();
+        while let Some((index, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && !COMMENT_MARKERS.contains(c)) {
+            end = index + c.len_utf8();
+            column += 1;
+        }
+        let text = &line[start..end];
+        tokens.push(Token {
+            kind: classify(text),
+            text,
+            span: Span { start, end },
+            column: token_column,
+        });
+    }
+    tokens
+}
+
+fn classify(text: &str) -> TokenKind {
+    if is_label(text) {
+        TokenKind::Label
+    } else if is_identifier(text) {
+        TokenKind::Identifier
+    } else if is_number(text) {
+        TokenKind::Number
+    } else {
+        TokenKind::Invalid
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Whether the whole text is a variable or channel name.
+pub fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && chars.all(is_name_char)
+}
+
+/// Whether the whole text is a label name, the dot included.
+pub fn is_label(text: &str) -> bool {
+    text.strip_prefix('.').is_some_and(|name| !name.is_empty() && name.chars().all(is_name_char))
+}
+
+pub fn is_number(text: &str) -> bool {
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(line: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(line).into_iter().map(|token| (token.kind, token.text)).collect()
+    }
+
+    #[test]
+    fn tokenize_instructions_and_labels() {
+        assert_eq!(
+            kinds("  LOAD_VAL -12 .loop_1 x-y .a!b 0x"),
+            vec![
+                (TokenKind::Identifier, "LOAD_VAL"),
+                (TokenKind::Number, "-12"),
+                (TokenKind::Label, ".loop_1"),
+                (TokenKind::Invalid, "x-y"),
+                (TokenKind::Invalid, ".a!b"),
+                (TokenKind::Invalid, "0x"),
+            ]
+        );
+        assert_eq!(kinds(" \t "), vec![]);
+        assert_eq!(kinds("."), vec![(TokenKind::Invalid, ".")]);
+    }
+
+    #[test]
+    fn tokenize_comments() {
+        assert_eq!(
+            kinds("GOTO .loop;again # still ; the comment  "),
+            vec![
+                (TokenKind::Identifier, "GOTO"),
+                (TokenKind::Label, ".loop"),
+                (TokenKind::Comment, ";again # still ; the comment"),
+            ]
+        );
+        assert_eq!(kinds("# only"), vec![(TokenKind::Comment, "# only")]);
+    }
+
+    #[test]
+    fn token_spans_and_columns() {
+        let line = "\té READ_VAR  x";
+        let tokens = tokenize(line);
+        assert_eq!(
+            tokens.iter().map(|token| (token.span, token.column)).collect::<Vec<_>>(),
+            vec![
+                (Span { start: 1, end: 3 }, 2),
+                (Span { start: 4, end: 12 }, 4),
+                (Span { start: 14, end: 15 }, 14),
+            ]
+        );
+        assert_eq!(&line[tokens[1].span.start..tokens[1].span.end], "READ_VAR");
+        assert_eq!(tokens[1].end_column(), 12);
+    }
+}
@@ -1,31 +1,113 @@
+pub mod binary;
+pub mod disassembler;
+pub mod formatter;
 pub mod instruction;
+pub mod lexer;
+pub mod opcode;
+pub mod optimizer;
 
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use instruction::Instruction;
-use lazy_static::lazy_static;
-use regex::Regex;
+use lexer::{Token, TokenKind};
 use crate::config::MaxInstructionNumber;
+use crate::error::{ParseError, ParseErrorKind, ParseErrors};
 
-#[derive(Debug, PartialEq)]
+/// Where an instruction starts in the source file, both counting from 1.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SourcePosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Most instructions a program can have, so that every label target fits in `MaxInstructionNumber`, including a
+/// label after the last instruction.
+pub const MAX_INSTRUCTIONS: usize = MaxInstructionNumber::MAX as usize;
+
+/// Characters that start a comment, it lasts until the end of the line.
+pub const COMMENT_MARKERS: [char; 2] = [';', '#'];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CommentPlacement {
+    /// The comment is the only thing on its line.
+    OwnLine,
+    /// The comment follows the label on the same line.
+    AfterLabel(String),
+    /// The comment follows the instruction on the same line.
+    AfterInstruction,
+}
+
+/// Comment of the source file, kept so that tools printing the program can write it back.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Comment {
+    /// The comment with its marker, without trailing whitespace.
+    pub text: String,
+    pub line: usize,
+    /// Index of the instruction on the line of the comment, or of the first instruction after it.
+    pub instruction_index: usize,
+    pub placement: CommentPlacement,
+}
+
+/// Code of a source line.
+pub(crate) enum Statement {
+    Label(String),
+    Instruction(Instruction),
+}
+
+/// Checks the grammar of the tokens of a line: a label alone or a mnemonic with its operands, optionally
+/// followed by a comment. Returns `None` for a line without code. Errors have the column of the offending
+/// token, or the column after the last token if an operand is missing, but no line.
+pub(crate) fn parse_statement(tokens: &[Token]) -> Result<Option<Statement>, ParseError> {
+    let code = match tokens.split_last() {
+        Some((last, code)) if last.kind == TokenKind::Comment => code,
+        _ => tokens,
+    };
+    let (first, operands) = match code.split_first() {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    if first.text.starts_with('.') {
+        if first.kind != TokenKind::Label {
+            let kind = ParseErrorKind::InvalidLabelName(first.text.to_string());
+            return Err(ParseError::new(kind).at_column(first.column));
+        }
+        if let Some(token) = operands.first() {
+            let kind = ParseErrorKind::UnexpectedToken(token.text.to_string());
+            return Err(ParseError::new(kind).at_column(token.column));
+        }
+        return Ok(Some(Statement::Label(first.text.to_string())));
+    }
+    Instruction::new(first, operands).map(|instruction| Some(Statement::Instruction(instruction)))
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ByteCode {
     instructions: Vec<Instruction>,
     labels: HashMap<String, MaxInstructionNumber>,
+    /// Source position of every parsed instruction, by instruction index.
+    positions: Vec<SourcePosition>,
+    /// Comments of the source file in line order.
+    comments: Vec<Comment>,
+}
+
+/// Programs with the same instructions and labels are equal wherever they were parsed from,
+/// source positions and comments aren't compared.
+impl PartialEq for ByteCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.instructions == other.instructions && self.labels == other.labels
+    }
 }
 
 impl ByteCode {
     pub fn new() -> Self {
-        ByteCode {
-            instructions: Vec::new(),
-            labels: HashMap::new(),
-        }
+        ByteCode::default()
     }
 
-    pub fn parse_file(source_file: &str) -> Result<Self, String> {
+    pub fn parse_file(source_file: &str) -> Result<Self, ParseErrors> {
         let file = match File::open(source_file) {
             Ok(file) => file,
-            Err(e) => return Err(format!("Unable to open file: {}", e)),
+            Err(e) => return Err(ParseError::new(ParseErrorKind::OpenFile(e.to_string())).into()),
         };
 
         let mut reader = BufReader::new(file);
@@ -36,61 +118,149 @@ impl ByteCode {
         self.instructions.get(index)
     }
 
-    pub fn get_label(&self, label_name: &str) -> Result<MaxInstructionNumber, String> {
-        if !self.labels.contains_key(label_name) {
-            return Err(format!("Label with name: {} doesn't exist", label_name))
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Label names with the index of the instruction they point to.
+    pub fn labels(&self) -> &HashMap<String, MaxInstructionNumber> {
+        &self.labels
+    }
+
+    pub fn get_position(&self, index: usize) -> Option<SourcePosition> {
+        self.positions.get(index).copied()
+    }
+
+    pub fn positions(&self) -> &[SourcePosition] {
+        &self.positions
+    }
+
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    pub fn parse_instructions<R: BufRead>(reader: &mut R) -> Result<Self, ParseErrors> {
+        let (program, errors) = ByteCode::parse(reader, false);
+        if !errors.is_empty() {
+            return Err(ParseErrors(errors));
         }
-        Ok(*self.labels.get(label_name).unwrap())
+        Ok(program)
     }
 
-    fn parse_instructions<R: BufRead>(reader: &mut R) -> Result<Self, String> {
+    /// Keeps going after an invalid line and returns every error in source order, together with the
+    /// instructions and labels of the valid lines. Jumps to undefined labels are kept without a target,
+    /// so the program can only be run if there are no errors.
+    pub fn parse_instructions_recovering<R: BufRead>(reader: &mut R) -> (Self, Vec<ParseError>) {
+        ByteCode::parse(reader, true)
+    }
+
+    pub fn parse_file_recovering(source_file: &str) -> (Self, Vec<ParseError>) {
+        match File::open(source_file) {
+            Ok(file) => ByteCode::parse_instructions_recovering(&mut BufReader::new(file)),
+            Err(e) => (ByteCode::new(), vec![ParseError::new(ParseErrorKind::OpenFile(e.to_string()))]),
+        }
+    }
+
+    fn parse<R: BufRead>(reader: &mut R, recover: bool) -> (Self, Vec<ParseError>) {
         let mut program = ByteCode::new();
-        let mut instruction_number = 0;
+        let mut errors = Vec::new();
+        let mut too_many_instructions = false;
         for (index, line) in reader.lines().enumerate() {
+            if !recover && !errors.is_empty() {
+                return (program, errors);
+            }
             let ln = match line {
                 Ok(line) => line,
-                Err(e) => return Err(format!("Error reading line #{}: {}", index + 1, e)),
+                Err(e) => {
+                    errors.push(ParseError::new(ParseErrorKind::ReadLine(e.to_string())).at_line(index + 1));
+                    break;
+                }
             };
-            if ln.is_empty() {
-                continue;
-            }
-            let s_split = ln
-                .split_whitespace()
-                .map(|s| s.to_string())
-                .collect::<Vec<_>>();
-
-            if ByteCode::is_label(&s_split[0]) {
-                let label = program.parse_label(&s_split[0], instruction_number);
-                if let Err(e) = label {
-                    return Err(format!("Unable to parse line #{}: {}", index + 1, e));
+            let tokens = lexer::tokenize(&ln);
+            let comment = tokens
+                .last()
+                .filter(|token| token.kind == TokenKind::Comment)
+                .map(|token| Comment {
+                    text: token.text.to_string(),
+                    line: index + 1,
+                    instruction_index: program.instructions.len(),
+                    placement: CommentPlacement::OwnLine,
+                });
+            match parse_statement(&tokens) {
+                Ok(None) => program.comments.extend(comment),
+                Ok(Some(Statement::Label(label))) => {
+                    let instruction_number = MaxInstructionNumber::try_from(program.instructions.len())
+                        .expect("the number of instructions is limited to MAX_INSTRUCTIONS");
+                    match program.parse_label(&label, instruction_number) {
+                        Ok(()) => program.comments.extend(comment.map(|comment| Comment {
+                            placement: CommentPlacement::AfterLabel(label),
+                            ..comment
+                        })),
+                        Err(e) => errors.push(e.at_line(index + 1).at_column(tokens[0].column)),
+                    }
+                }
+                Ok(Some(Statement::Instruction(_))) if program.instructions.len() == MAX_INSTRUCTIONS => {
+                    if !too_many_instructions {
+                        errors.push(
+                            ParseError::new(ParseErrorKind::TooManyInstructions(MAX_INSTRUCTIONS))
+                                .at_line(index + 1)
+                                .at_column(tokens[0].column),
+                        );
+                        too_many_instructions = true;
+                    }
                 }
-            } else {
-                let instruction = Instruction::new(&s_split[0], s_split[1..].to_vec());
-                if let Err(e) = instruction {
-                    return Err(format!("Unable to parse line #{}: {}", index + 1, e));
-                } else {
-                    program.instructions.push(instruction.unwrap())
+                Ok(Some(Statement::Instruction(instruction))) => {
+                    program.comments.extend(comment.map(|comment| Comment {
+                        placement: CommentPlacement::AfterInstruction,
+                        ..comment
+                    }));
+                    program.instructions.push(instruction);
+                    program.positions.push(SourcePosition {
+                        line: index + 1,
+                        column: tokens[0].column,
+                    });
                 }
-                instruction_number += 1;
+                Err(e) => errors.push(e.at_line(index + 1)),
             }
         }
-        if program.instructions.is_empty() {
-            return Err("Empty program".to_string());
+        if !recover && !errors.is_empty() {
+            return (program, errors);
+        }
+        if program.instructions.is_empty() && errors.is_empty() {
+            errors.push(ParseError::new(ParseErrorKind::EmptyProgram));
+            return (program, errors);
         }
-        return Ok(program);
+        errors.append(&mut program.resolve_labels());
+        errors.sort_by_key(|error| error.line);
+        (program, errors)
     }
 
-    pub fn is_label(label: &str) -> bool {
-        lazy_static! {
-            static ref LABEL_RE: Regex =
-                Regex::new(r"^\.[a-zA-Z_0-9][a-zA-Z0-9_]*").expect("Invalid regex");
+    /// Replaces label operands with instruction indices, returns an error for every undefined label.
+    fn resolve_labels(&mut self) -> Vec<ParseError> {
+        let mut errors = Vec::new();
+        for (instruction, position) in self.instructions.iter_mut().zip(&self.positions) {
+            let label = match instruction.label() {
+                Some(label) => label,
+                None => continue,
+            };
+            match self.labels.get(label) {
+                Some(target) => instruction.target = Some(*target),
+                None => errors.push(
+                    ParseError::for_instruction(instruction.name, ParseErrorKind::UndefinedLabel(label.to_string()))
+                        .at_line(position.line),
+                ),
+            }
         }
-        LABEL_RE.is_match(label)
+        errors
+    }
+
+    pub fn is_label(label: &str) -> bool {
+        lexer::is_label(label)
     }
 
-    fn parse_label(&mut self, label_name: &str, instruction_number: u16) -> Result<(), String>{
+    fn parse_label(&mut self, label_name: &str, instruction_number: MaxInstructionNumber) -> Result<(), ParseError>{
         if self.labels.contains_key(label_name) {
-            Err(format!("duplicated label: {}", label_name))?;
+            Err(ParseError::new(ParseErrorKind::DuplicatedLabel(label_name.to_string())))?;
         }
         self.labels.insert(label_name.to_string(), instruction_number);
         Ok(())
@@ -99,7 +269,6 @@ impl ByteCode {
 
 #[cfg(test)]
 mod tests {
-    use crate::VariableValue;
     use super::*;
 
     #[test]
@@ -127,9 +296,12 @@ mod tests {
         let code = "READ_VAR x y";
         let mut reader = BufReader::new(code.as_bytes());
         let result = ByteCode::parse_instructions(&mut reader);
+        let error = result.unwrap_err();
+        assert_eq!(error.errors()[0].line, Some(1));
+        assert_eq!(error.errors()[0].kind, ParseErrorKind::ArgumentCount { expected: 1, got: 2 });
         assert_eq!(
-            result,
-            Err("Unable to parse line #1: Error creating read instruction: expected 1 argument, got 2".to_string())
+            error.to_string(),
+            "Unable to parse line #1, column 12: Error creating read instruction: expected 1 argument, got 2"
         );
     }
 
@@ -140,7 +312,7 @@ mod tests {
         let result = ByteCode::parse_instructions(&mut reader);
         assert_eq!(
             result,
-            Err("Empty program".to_string())
+            Err(ParseError::new(ParseErrorKind::EmptyProgram).into())
         );
     }
 
@@ -151,7 +323,153 @@ mod tests {
         let result = ByteCode::parse_instructions(&mut reader);
         assert!(result.is_ok());
         let bytecode = result.ok().unwrap();
-        assert_eq!(*bytecode.labels.get(".label").unwrap(), 2 as VariableValue);
-        assert_eq!(bytecode.instructions.len(), 3)
+        assert_eq!(*bytecode.labels.get(".label").unwrap(), 2 as MaxInstructionNumber);
+        assert_eq!(bytecode.instructions.len(), 3);
+        assert_eq!(bytecode.instructions[2].target, Some(2));
+    }
+
+    #[test]
+    fn create_program_with_positions() {
+        let code = "LOAD_VAL 1\n\n.label\n    READ_VAR x\n\tGOTO .label";
+        let mut reader = BufReader::new(code.as_bytes());
+        let bytecode = ByteCode::parse_instructions(&mut reader).ok().unwrap();
+        assert_eq!(
+            bytecode.positions(),
+            &[
+                SourcePosition { line: 1, column: 1 },
+                SourcePosition { line: 4, column: 5 },
+                SourcePosition { line: 5, column: 2 },
+            ]
+        );
+        assert_eq!(bytecode.get_position(3), None);
+    }
+
+    #[test]
+    fn create_program_with_undefined_labels() {
+        let code = "LOAD_VAL 1\nGOTO .first\n.label\nJUMP .label\n\nCALL .second";
+        let mut reader = BufReader::new(code.as_bytes());
+        let result = ByteCode::parse_instructions(&mut reader);
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Unable to parse line #2: undefined label .first\nUnable to parse line #6: undefined label .second".to_string())
+        );
+    }
+
+    #[test]
+    fn create_program_recovering_from_errors() {
+        let code = "LOAD_VAL 1\nPUSH 2\n.label\nREAD_VAR 0x\n   \nADD 3\n.label\nGOTO .label\nJUMP .missing";
+        let mut reader = BufReader::new(code.as_bytes());
+        let (bytecode, errors) = ByteCode::parse_instructions_recovering(&mut reader);
+        assert_eq!(
+            errors.iter().map(|error| (error.line, error.kind.clone())).collect::<Vec<_>>(),
+            vec![
+                (Some(2), ParseErrorKind::UnknownInstruction("PUSH".to_string())),
+                (Some(4), ParseErrorKind::InvalidVariableName("0x".to_string())),
+                (Some(6), ParseErrorKind::ArgumentCount { expected: 0, got: 1 }),
+                (Some(7), ParseErrorKind::DuplicatedLabel(".label".to_string())),
+                (Some(9), ParseErrorKind::UndefinedLabel(".missing".to_string())),
+            ]
+        );
+        assert_eq!(bytecode.instructions.len(), 3);
+        assert_eq!(bytecode.instructions[1].target, Some(1));
+        assert_eq!(bytecode.instructions[2].target, None);
+        assert_eq!(bytecode.get_position(1), Some(SourcePosition { line: 8, column: 1 }));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn create_program_stopping_at_first_error() {
+        let code = "PUSH 2\nADD 3";
+        let mut reader = BufReader::new(code.as_bytes());
+        let result = ByteCode::parse_instructions(&mut reader);
+        assert_eq!(
+            result,
+            Err(ParseError::new(ParseErrorKind::UnknownInstruction("PUSH".to_string())).at_line(1).at_column(1).into())
+        );
+    }
+
+    #[test]
+    fn create_program_with_comments() {
+        let code = "; counts down\nLOAD_VAL 3 # start\n.loop ; body\n  # decrement\nGOTO .loop;again\n;";
+        let mut reader = BufReader::new(code.as_bytes());
+        let bytecode = ByteCode::parse_instructions(&mut reader).unwrap();
+        assert_eq!(bytecode.instructions().len(), 2);
+        let comment = |text: &str, line, instruction_index, placement| Comment {
+            text: text.to_string(),
+            line,
+            instruction_index,
+            placement,
+        };
+        assert_eq!(
+            bytecode.comments(),
+            &[
+                comment("; counts down", 1, 0, CommentPlacement::OwnLine),
+                comment("# start", 2, 0, CommentPlacement::AfterInstruction),
+                comment("; body", 3, 1, CommentPlacement::AfterLabel(".loop".to_string())),
+                comment("# decrement", 4, 1, CommentPlacement::OwnLine),
+                comment(";again", 5, 1, CommentPlacement::AfterInstruction),
+                comment(";", 6, 2, CommentPlacement::OwnLine),
+            ]
+        );
+        assert_eq!(bytecode, ByteCode::parse_instructions(&mut BufReader::new("LOAD_VAL 3\n.loop\nGOTO .loop".as_bytes())).unwrap());
+    }
+
+    #[test]
+    fn create_program_with_errors_in_commented_lines() {
+        let code = "LOAD_VAL 1 ; one\nADD 2 ; two\n; only a comment\nREAD_VAR ; no variable";
+        let mut reader = BufReader::new(code.as_bytes());
+        let (_, errors) = ByteCode::parse_instructions_recovering(&mut reader);
+        assert_eq!(
+            errors.iter().map(|error| error.to_string()).collect::<Vec<_>>(),
+            vec![
+                "Unable to parse line #2, column 5: Error creating add instruction: expected 0 argument, got 1".to_string(),
+                "Unable to parse line #4, column 9: Error creating read instruction: expected 1 argument, got 0".to_string(),
+            ]
+        );
+        assert_eq!(
+            ByteCode::parse_instructions(&mut BufReader::new("; nothing\n# here".as_bytes())),
+            Err(ParseError::new(ParseErrorKind::EmptyProgram).into())
+        );
+    }
+
+    #[test]
+    fn reject_invalid_names_and_tokens_after_labels() {
+        let code = "WRITE_VAR x-y\n.a!b\n  .label JUMP .label\nSEND_CHANNEL out$\nGOTO .end?\n.label ; comment\nLOAD_VAL";
+        let mut reader = BufReader::new(code.as_bytes());
+        let (bytecode, errors) = ByteCode::parse_instructions_recovering(&mut reader);
+        assert_eq!(
+            errors.iter().map(|error| error.to_string()).collect::<Vec<_>>(),
+            vec![
+                "Unable to parse line #1, column 11: Invalid variable name x-y".to_string(),
+                "Unable to parse line #2, column 1: Invalid label name: .a!b".to_string(),
+                "Unable to parse line #3, column 10: Unexpected token after label: JUMP".to_string(),
+                "Unable to parse line #4, column 14: Invalid channel name out$".to_string(),
+                "Unable to parse line #5, column 6: Invalid label name: .end?".to_string(),
+                "Unable to parse line #7, column 9: Error creating load instruction: expected 1 argument, got 0"
+                    .to_string(),
+            ]
+        );
+        assert_eq!(bytecode.labels().keys().collect::<Vec<_>>(), vec![".label"]);
+        assert!(ByteCode::is_label(".loop_2"));
+        assert!(!ByteCode::is_label(".loop-2"));
+    }
+
+    #[test]
+    fn reject_programs_with_too_many_instructions() {
+        let code = format!(
+            "LOAD_VAL 7\nJUMP .end\nRETURN_VALUE\n{}.end\nLOAD_VAL 9\nRETURN_VALUE",
+            "NOT\n".repeat(MAX_INSTRUCTIONS)
+        );
+        let (_, errors) = ByteCode::parse_instructions_recovering(&mut BufReader::new(code.as_bytes()));
+        assert_eq!(
+            errors,
+            vec![ParseError::new(ParseErrorKind::TooManyInstructions(MAX_INSTRUCTIONS))
+                .at_line(MAX_INSTRUCTIONS + 1)
+                .at_column(1)]
+        );
+
+        let code = format!("LOAD_VAL 7\nJUMP .end\nRETURN_VALUE\n{}.end", "NOT\n".repeat(MAX_INSTRUCTIONS - 3));
+        let bytecode = ByteCode::parse_instructions(&mut BufReader::new(code.as_bytes())).unwrap();
+        assert_eq!(bytecode.labels()[".end"], MaxInstructionNumber::MAX);
+        assert_eq!(bytecode.instructions()[1].target, Some(MaxInstructionNumber::MAX));
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use super::instruction::Instruction;
+use super::{ByteCode, SourcePosition};
+use crate::config::{InstructionName, VariableValue};
+
+/// Instruction lowered for execution: operands are inlined and labels are replaced with instruction indices.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum OpCode {
+    Load(VariableValue),
+    /// Writes the variable slot, see `Program::variables`.
+    Write(usize),
+    /// Reads the variable slot, see `Program::variables`.
+    Read(usize),
+    Add,
+    Sub,
+    Multiply,
+    Div,
+    Mod,
+    Neg,
+    And,
+    Or,
+    Xor,
+    Not,
+    Shl,
+    Shr,
+    Return,
+    Equal,
+    Greater,
+    Less,
+    Dup,
+    Pop,
+    Goto(usize),
+    Jump(usize),
+    JumpIf(usize),
+    JumpIfNot(usize),
+    Spawn(usize),
+    /// Sends to the channel slot, see `Program::channels`.
+    Send(usize),
+    /// Receives from the channel slot, see `Program::channels`.
+    Recv(usize),
+    Call(usize),
+    Ret,
+}
+
+impl OpCode {
+    pub fn name(&self) -> InstructionName {
+        match self {
+            OpCode::Load(_) => InstructionName::LOAD,
+            OpCode::Write(_) => InstructionName::WRITE,
+            OpCode::Read(_) => InstructionName::READ,
+            OpCode::Add => InstructionName::ADD,
+            OpCode::Sub => InstructionName::SUB,
+            OpCode::Multiply => InstructionName::MULTIPLY,
+            OpCode::Div => InstructionName::DIV,
+            OpCode::Mod => InstructionName::MOD,
+            OpCode::Neg => InstructionName::NEG,
+            OpCode::And => InstructionName::AND,
+            OpCode::Or => InstructionName::OR,
+            OpCode::Xor => InstructionName::XOR,
+            OpCode::Not => InstructionName::NOT,
+            OpCode::Shl => InstructionName::SHL,
+            OpCode::Shr => InstructionName::SHR,
+            OpCode::Return => InstructionName::RETURN,
+            OpCode::Equal => InstructionName::EQUAL,
+            OpCode::Greater => InstructionName::GREATER,
+            OpCode::Less => InstructionName::LESS,
+            OpCode::Dup => InstructionName::DUP,
+            OpCode::Pop => InstructionName::POP,
+            OpCode::Goto(_) => InstructionName::GOTO,
+            OpCode::Jump(_) => InstructionName::JUMP,
+            OpCode::JumpIf(_) => InstructionName::JUMPIF,
+            OpCode::JumpIfNot(_) => InstructionName::JUMPIFNOT,
+            OpCode::Spawn(_) => InstructionName::SPAWN,
+            OpCode::Send(_) => InstructionName::SEND,
+            OpCode::Recv(_) => InstructionName::RECV,
+            OpCode::Call(_) => InstructionName::CALL,
+            OpCode::Ret => InstructionName::RET,
+        }
+    }
+
+    /// Expects an instruction of a parsed `ByteCode`, so its operands and label target are present.
+    /// `variable_slot` and `channel_slot` map a variable or channel name to its slot.
+    pub fn lower(
+        instruction: &Instruction,
+        variable_slot: &mut impl FnMut(&str) -> usize,
+        channel_slot: &mut impl FnMut(&str) -> usize,
+    ) -> Self {
+        let name = || instruction.variable.as_deref().unwrap();
+        let mut slot = || variable_slot(name());
+        let mut channel = || channel_slot(name());
+        let target = || instruction.target.unwrap() as usize;
+        match instruction.name {
+            InstructionName::LOAD => OpCode::Load(instruction.arg.unwrap()),
+            InstructionName::WRITE => OpCode::Write(slot()),
+            InstructionName::READ => OpCode::Read(slot()),
+            InstructionName::ADD => OpCode::Add,
+            InstructionName::SUB => OpCode::Sub,
+            InstructionName::MULTIPLY => OpCode::Multiply,
+            InstructionName::DIV => OpCode::Div,
+            InstructionName::MOD => OpCode::Mod,
+            InstructionName::NEG => OpCode::Neg,
+            InstructionName::AND => OpCode::And,
+            InstructionName::OR => OpCode::Or,
+            InstructionName::XOR => OpCode::Xor,
+            InstructionName::NOT => OpCode::Not,
+            InstructionName::SHL => OpCode::Shl,
+            InstructionName::SHR => OpCode::Shr,
+            InstructionName::RETURN => OpCode::Return,
+            InstructionName::EQUAL => OpCode::Equal,
+            InstructionName::GREATER => OpCode::Greater,
+            InstructionName::LESS => OpCode::Less,
+            InstructionName::DUP => OpCode::Dup,
+            InstructionName::POP => OpCode::Pop,
+            InstructionName::GOTO => OpCode::Goto(target()),
+            InstructionName::JUMP => OpCode::Jump(target()),
+            InstructionName::JUMPIF => OpCode::JumpIf(target()),
+            InstructionName::JUMPIFNOT => OpCode::JumpIfNot(target()),
+            InstructionName::SPAWN => OpCode::Spawn(target()),
+            InstructionName::SEND => OpCode::Send(channel()),
+            InstructionName::RECV => OpCode::Recv(channel()),
+            InstructionName::CALL => OpCode::Call(target()),
+            InstructionName::RET => OpCode::Ret,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Program {
+    pub opcodes: Vec<OpCode>,
+    /// Variable names indexed by slot, variables are numbered in the order of their first use.
+    pub variables: Vec<String>,
+    /// Channel names indexed by slot, numbered like variables.
+    pub channels: Vec<String>,
+    /// Source position of every opcode, empty for programs that weren't parsed from text.
+    pub positions: Vec<SourcePosition>,
+}
+
+/// Names numbered in the order of their first use.
+#[derive(Default)]
+struct Slots {
+    slots: HashMap<String, usize>,
+    names: Vec<String>,
+}
+
+impl Slots {
+    fn slot(&mut self, name: &str) -> usize {
+        *self.slots.entry(name.to_string()).or_insert_with(|| {
+            self.names.push(name.to_string());
+            self.names.len() - 1
+        })
+    }
+}
+
+/// Lowers every instruction of the program, the opcode at index `i` comes from the instruction at index `i`.
+pub fn compile(bytecode: &ByteCode) -> Program {
+    let mut variables = Slots::default();
+    let mut channels = Slots::default();
+    let opcodes = bytecode
+        .instructions()
+        .iter()
+        .map(|instruction| {
+            OpCode::lower(instruction, &mut |name| variables.slot(name), &mut |name| channels.slot(name))
+        })
+        .collect();
+    Program {
+        opcodes,
+        variables: variables.names,
+        channels: channels.names,
+        positions: bytecode.positions().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use crate::bytecode::lexer::tokenize;
+
+    #[test]
+    fn lower_instruction_with_operands() {
+        let mut variable_slot = |_: &str| 3;
+        let mut channel_slot = |_: &str| 7;
+
+        let tokens = tokenize("LOAD_VAL 5");
+        let load = Instruction::new(&tokens[0], &tokens[1..]).ok().unwrap();
+        assert_eq!(OpCode::lower(&load, &mut variable_slot, &mut channel_slot), OpCode::Load(5));
+
+        let tokens = tokenize("WRITE_VAR x");
+        let write = Instruction::new(&tokens[0], &tokens[1..]).ok().unwrap();
+        assert_eq!(OpCode::lower(&write, &mut variable_slot, &mut channel_slot), OpCode::Write(3));
+        assert_eq!(OpCode::lower(&write, &mut variable_slot, &mut channel_slot).name(), write.name);
+
+        let tokens = tokenize("SEND_CHANNEL x");
+        let send = Instruction::new(&tokens[0], &tokens[1..]).ok().unwrap();
+        assert_eq!(OpCode::lower(&send, &mut variable_slot, &mut channel_slot), OpCode::Send(7));
+    }
+
+    #[test]
+    fn compile_program() {
+        let code = "LOAD_VAL 1\n.label\nWRITE_VAR x\nREAD_VAR y\nREAD_VAR x\nSEND_CHANNEL y\nRECV_CHANNEL x\n\
+            RECV_CHANNEL y\nGOTO .label";
+        let mut reader = BufReader::new(code.as_bytes());
+        let bytecode = ByteCode::parse_instructions(&mut reader).ok().unwrap();
+        let program = compile(&bytecode);
+        assert_eq!(
+            program.opcodes,
+            vec![
+                OpCode::Load(1),
+                OpCode::Write(0),
+                OpCode::Read(1),
+                OpCode::Read(0),
+                OpCode::Send(0),
+                OpCode::Recv(1),
+                OpCode::Recv(0),
+                OpCode::Goto(1),
+            ]
+        );
+        assert_eq!(program.variables, vec!["x".to_string(), "y".to_string()]);
+        assert_eq!(program.channels, vec!["y".to_string(), "x".to_string()]);
+    }
+}
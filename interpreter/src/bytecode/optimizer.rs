@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+use super::instruction::Instruction;
+use super::{ByteCode, Comment, CommentPlacement};
+use crate::analysis::stack::verify_stack;
+use crate::analysis::{flow, Flow};
+use crate::config::{InstructionName, MaxInstructionNumber, OverflowMode, VariableValue};
+use crate::interpreter::{arithmetic, bitwise};
+
+/// Returns an equivalent program with constant expressions folded, no-op sequences removed and instructions
+/// that can't be reached from the first instruction, a `CALL` or a `SPAWN` dropped. The passes repeat until
+/// nothing changes. Labels keep pointing to the same code, a label of a removed instruction moves to the next
+/// instruction that is kept. Expressions that fail at runtime, e.g. division by zero or overflow in
+/// `OverflowMode::Checked`, are left as they are, so the optimized program fails with the same error.
+/// A pass that would remove every instruction isn't applied: the parser, `disassemble` and the binary loader
+/// all reject an empty program.
+pub fn optimize(bytecode: &ByteCode) -> ByteCode {
+    let mut program = bytecode.clone();
+    loop {
+        let stack_verified = verify_stack(&program).is_ok();
+        let optimized = remove_unreachable(&peephole(&program, stack_verified));
+        if optimized == program || optimized.instructions.is_empty() {
+            return program;
+        }
+        program = optimized;
+    }
+}
+
+/// Rewrites short sequences of instructions. A sequence is only rewritten if no label points inside it.
+/// `stack_verified` tells that `verify_stack` accepts the program, so no instruction runs on a too short stack.
+fn peephole(bytecode: &ByteCode, stack_verified: bool) -> ByteCode {
+    let instructions = &bytecode.instructions;
+    let labelled: HashSet<usize> = bytecode.labels.values().map(|target| *target as usize).collect();
+    let mut emitted = vec![Vec::new(); instructions.len()];
+    let mut index = 0;
+    while index < instructions.len() {
+        let window = &instructions[index..];
+        let unlabelled = |length: usize| window.len() >= length && (1..length).all(|i| !labelled.contains(&(index + i)));
+        let (replacement, length) = if let Some(value) = unlabelled(3).then(|| fold_binary(window)).flatten() {
+            (vec![load(value)], 3)
+        } else if let Some(value) = unlabelled(2).then(|| fold_unary(window)).flatten() {
+            (vec![load(value)], 2)
+        } else if let Some(replacement) = unlabelled(2).then(|| fold_branch(window)).flatten() {
+            (replacement, 2)
+        } else if unlabelled(2) && pushes_unused_value(window, stack_verified) {
+            (vec![], 2)
+        } else if let Some(replacement) = jump_to_next(index, &window[0]) {
+            (replacement, 1)
+        } else {
+            (vec![window[0].clone()], 1)
+        };
+        emitted[index] = replacement;
+        index += length;
+    }
+    rebuild(bytecode, emitted)
+}
+
+/// `LOAD_VAL a`, `LOAD_VAL b`, operation: the second value is popped first.
+fn fold_binary(window: &[Instruction]) -> Option<VariableValue> {
+    let value2 = loaded_value(&window[0])?;
+    let value1 = loaded_value(&window[1])?;
+    let mode = OverflowMode::Checked;
+    match window[2].name {
+        InstructionName::ADD => arithmetic::add(mode, value1, value2).ok(),
+        InstructionName::SUB => arithmetic::sub(mode, value1, value2).ok(),
+        InstructionName::MULTIPLY => arithmetic::multiply(mode, value1, value2).ok(),
+        InstructionName::DIV => arithmetic::div(mode, value1, value2).ok(),
+        InstructionName::MOD => arithmetic::modulo(mode, value1, value2).ok(),
+        InstructionName::AND => Some(bitwise::and(value1, value2)),
+        InstructionName::OR => Some(bitwise::or(value1, value2)),
+        InstructionName::XOR => Some(bitwise::xor(value1, value2)),
+        InstructionName::SHL => bitwise::shl(value1, value2).ok(),
+        InstructionName::SHR => bitwise::shr(value1, value2).ok(),
+        InstructionName::GREATER => Some((value1 > value2) as VariableValue),
+        InstructionName::LESS => Some((value1 < value2) as VariableValue),
+        InstructionName::EQUAL => Some((value1 == value2) as VariableValue),
+        _ => None,
+    }
+}
+
+fn fold_unary(window: &[Instruction]) -> Option<VariableValue> {
+    let value = loaded_value(&window[0])?;
+    match window[1].name {
+        InstructionName::NEG => arithmetic::neg(OverflowMode::Checked, value).ok(),
+        InstructionName::NOT => Some(bitwise::not(value)),
+        _ => None,
+    }
+}
+
+/// A conditional jump of a loaded value becomes `JUMP` if it is always taken and disappears otherwise.
+fn fold_branch(window: &[Instruction]) -> Option<Vec<Instruction>> {
+    let value = loaded_value(&window[0])?;
+    let jump_if = match window[1].name {
+        InstructionName::GOTO | InstructionName::JUMPIF => true,
+        InstructionName::JUMPIFNOT => false,
+        _ => return None,
+    };
+    if (value != 0) != jump_if {
+        return Some(vec![]);
+    }
+    Some(vec![Instruction {
+        name: InstructionName::JUMP,
+        ..window[1].clone()
+    }])
+}
+
+/// `LOAD_VAL` followed by `POP`, or `DUP` followed by `POP` if the stack can't be empty, otherwise removing
+/// them would hide the stack underflow of `DUP`.
+fn pushes_unused_value(window: &[Instruction], stack_verified: bool) -> bool {
+    let pushes = match window[0].name {
+        InstructionName::LOAD => true,
+        InstructionName::DUP => stack_verified,
+        _ => false,
+    };
+    pushes && window[1].name == InstructionName::POP
+}
+
+/// A jump to the next instruction is removed, a conditional one still has to pop the condition.
+fn jump_to_next(index: usize, instruction: &Instruction) -> Option<Vec<Instruction>> {
+    match flow(instruction) {
+        Flow::Jump(target) if target == index + 1 => Some(vec![]),
+        Flow::Branch(target) if target == index + 1 => Some(vec![Instruction {
+            name: InstructionName::POP,
+            arg: None,
+            variable: None,
+            target: None,
+        }]),
+        _ => None,
+    }
+}
+
+fn loaded_value(instruction: &Instruction) -> Option<VariableValue> {
+    match instruction.name {
+        InstructionName::LOAD => instruction.arg,
+        _ => None,
+    }
+}
+
+fn load(value: VariableValue) -> Instruction {
+    Instruction {
+        name: InstructionName::LOAD,
+        arg: Some(value),
+        variable: None,
+        target: None,
+    }
+}
+
+fn remove_unreachable(bytecode: &ByteCode) -> ByteCode {
+    let instructions = &bytecode.instructions;
+    let mut reachable = vec![false; instructions.len()];
+    let mut worklist = vec![0];
+    while let Some(index) = worklist.pop() {
+        if index >= instructions.len() || reachable[index] {
+            continue;
+        }
+        reachable[index] = true;
+        match flow(&instructions[index]) {
+            Flow::Next => worklist.push(index + 1),
+            Flow::Jump(target) => worklist.push(target),
+            Flow::Branch(target) | Flow::Call(target) | Flow::Spawn(target) => worklist.extend([index + 1, target]),
+            Flow::Return | Flow::Ret => {}
+        }
+    }
+    let emitted = instructions
+        .iter()
+        .zip(reachable)
+        .map(|(instruction, reachable)| if reachable { vec![instruction.clone()] } else { vec![] })
+        .collect();
+    rebuild(bytecode, emitted)
+}
+
+/// Builds a program from the instructions emitted for every instruction of `bytecode`. Emitted instructions
+/// take the source position of the instruction they replace, labels and comments move to the first instruction
+/// emitted at or after their old one and jump targets are resolved again. The comment of a removed instruction
+/// is kept on its own line.
+fn rebuild(bytecode: &ByteCode, emitted: Vec<Vec<Instruction>>) -> ByteCode {
+    let mut program = ByteCode::new();
+    let mut new_indices = Vec::with_capacity(emitted.len() + 1);
+    let removed: Vec<bool> = emitted.iter().map(|instructions| instructions.is_empty()).collect();
+    for (index, instructions) in emitted.into_iter().enumerate() {
+        new_indices.push(instruction_number(&program));
+        if let Some(position) = bytecode.get_position(index) {
+            program.positions.extend(instructions.iter().map(|_| position));
+        }
+        program.instructions.extend(instructions);
+    }
+    new_indices.push(instruction_number(&program));
+    program.labels = bytecode
+        .labels
+        .iter()
+        .map(|(label, target)| (label.clone(), new_indices[*target as usize]))
+        .collect();
+    for instruction in &mut program.instructions {
+        instruction.target = instruction.label().map(|label| program.labels[label]);
+    }
+    program.comments = bytecode
+        .comments
+        .iter()
+        .map(|comment| Comment {
+            instruction_index: new_indices[comment.instruction_index] as usize,
+            placement: match comment.placement {
+                CommentPlacement::AfterInstruction if removed[comment.instruction_index] => CommentPlacement::OwnLine,
+                ref placement => placement.clone(),
+            },
+            ..comment.clone()
+        })
+        .collect();
+    program
+}
+
+/// Index after the last instruction. An optimized program is never longer than the original, so it fits.
+fn instruction_number(program: &ByteCode) -> MaxInstructionNumber {
+    MaxInstructionNumber::try_from(program.instructions.len())
+        .expect("the number of instructions is limited to MAX_INSTRUCTIONS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+    use crate::bytecode::disassembler::{disassemble, DisassemblyOptions};
+    use crate::error::RuntimeErrorKind;
+    use crate::interpreter::interpret;
+
+    fn parse(code: &str) -> ByteCode {
+        ByteCode::parse_instructions(&mut BufReader::new(code.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn optimize_resource_programs() {
+        for path in ["resources/simple_program.txt", "resources/program_with_nested_loops.txt"] {
+            let file = File::open(path).unwrap();
+            let bytecode = ByteCode::parse_instructions(&mut BufReader::new(file)).unwrap();
+            let optimized = optimize(&bytecode);
+            assert_eq!(interpret(optimized).unwrap(), interpret(bytecode).unwrap());
+        }
+    }
+
+    #[test]
+    fn fold_constants() {
+        let optimized = optimize(&parse("LOAD_VAL 2\nLOAD_VAL 10\nSUB\nLOAD_VAL 3\nMULTIPLY\nNOT\nNOT\nRETURN_VALUE"));
+        assert_eq!(optimized.instructions(), parse("LOAD_VAL 24\nRETURN_VALUE").instructions());
+    }
+
+    #[test]
+    fn keep_failing_expressions() {
+        let code = "LOAD_VAL 0\nLOAD_VAL 1\nDIV\nRETURN_VALUE";
+        assert_eq!(optimize(&parse(code)), parse(code));
+        let code = format!("LOAD_VAL 2\nLOAD_VAL {}\nMULTIPLY\nRETURN_VALUE", VariableValue::MAX);
+        assert_eq!(optimize(&parse(&code)), parse(&code));
+    }
+
+    #[test]
+    fn remove_unused_values_and_unreachable_code() {
+        let code = "LOAD_VAL 1\nDUP\nPOP\nLOAD_VAL 5\nPOP\nRETURN_VALUE\nLOAD_VAL 2\nRETURN_VALUE";
+        let optimized = optimize(&parse(code));
+        assert_eq!(optimized.instructions(), parse("LOAD_VAL 1\nRETURN_VALUE").instructions());
+        assert_eq!(optimized.get_position(1), parse(code).get_position(5));
+
+        let optimized = optimize(&parse("LOAD_VAL 1\nDUP ; copy\nPOP\nRETURN_VALUE ; result\nLOAD_VAL 2 ; never"));
+        assert_eq!(
+            optimized
+                .comments()
+                .iter()
+                .map(|comment| (comment.text.as_str(), comment.instruction_index, comment.placement.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("; copy", 1, CommentPlacement::OwnLine),
+                ("; result", 1, CommentPlacement::AfterInstruction),
+                ("; never", 2, CommentPlacement::OwnLine),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_one_instruction_of_a_program_without_effect() {
+        for code in ["LOAD_VAL 1\nPOP", "LOAD_VAL 1\nLOAD_VAL 2\nADD\nPOP", "LOAD_VAL 1\nDUP\nPOP\nPOP"] {
+            let optimized = optimize(&parse(code));
+            assert!(!optimized.instructions().is_empty());
+            assert_eq!(parse(&disassemble(&optimized, DisassemblyOptions::default())), optimized);
+            assert_eq!(ByteCode::from_binary(&optimized.to_binary()), Ok(optimized.clone()));
+            assert_eq!(interpret(optimized), Ok(None));
+        }
+    }
+
+    #[test]
+    fn keep_dup_on_a_possibly_empty_stack() {
+        let code = "DUP\nPOP\nLOAD_VAL 1\nRETURN_VALUE";
+        let optimized = optimize(&parse(code));
+        assert_eq!(optimized, parse(code));
+        assert_eq!(interpret(optimized), interpret(parse(code)));
+        assert_eq!(interpret(parse(code)).map_err(|e| e.kind), Err(RuntimeErrorKind::StackUnderflow));
+    }
+
+    #[test]
+    fn fold_branches_and_keep_labels() {
+        let code = "LOAD_VAL 0\nJUMP_IF_TRUE .end\nLOAD_VAL 1\nJUMP_IF_TRUE .end\nLOAD_VAL 2\n\
+            .end\nLOAD_VAL 3\nCALL .f\nRETURN_VALUE\n.f\nLOAD_VAL 4\nADD\nRET";
+        let optimized = optimize(&parse(code));
+        let expected = parse(".end\nLOAD_VAL 3\nCALL .f\nRETURN_VALUE\n.f\nLOAD_VAL 4\nADD\nRET");
+        assert_eq!(optimized.instructions(), expected.instructions());
+        assert_eq!(optimized.labels(), expected.labels());
+        assert_eq!(interpret(optimized).unwrap(), Some(7));
+    }
+
+    #[test]
+    fn keep_sequences_with_labels_inside() {
+        let code = "LOAD_VAL 1\nDUP\n.loop\nPOP\nLOAD_VAL 0\nGOTO .loop\nLOAD_VAL 2\nRETURN_VALUE";
+        let optimized = optimize(&parse(code));
+        let expected = parse("LOAD_VAL 1\nDUP\n.loop\nPOP\nLOAD_VAL 2\nRETURN_VALUE");
+        assert_eq!(optimized.instructions(), expected.instructions());
+        assert_eq!(optimized.labels(), expected.labels());
+    }
+}
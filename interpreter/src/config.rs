@@ -1,13 +1,64 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[cfg(not(any(
+    feature = "value-i16",
+    feature = "value-u32",
+    feature = "value-i32",
+    feature = "value-u64",
+    feature = "value-i64",
+    feature = "value-u128",
+    feature = "value-i128",
+)))]
 pub type VariableValue = u16;
+#[cfg(feature = "value-i16")]
+pub type VariableValue = i16;
+#[cfg(feature = "value-u32")]
+pub type VariableValue = u32;
+#[cfg(feature = "value-i32")]
+pub type VariableValue = i32;
+#[cfg(feature = "value-u64")]
+pub type VariableValue = u64;
+#[cfg(feature = "value-i64")]
+pub type VariableValue = i64;
+#[cfg(feature = "value-u128")]
+pub type VariableValue = u128;
+#[cfg(feature = "value-i128")]
+pub type VariableValue = i128;
+
+/// Whether `VariableValue` can hold negative values.
+pub const SIGNED_VALUES: bool = VariableValue::MIN != 0;
+
+const SELECTED_VALUE_TYPES: usize = cfg!(feature = "value-i16") as usize
+    + cfg!(feature = "value-u32") as usize
+    + cfg!(feature = "value-i32") as usize
+    + cfg!(feature = "value-u64") as usize
+    + cfg!(feature = "value-i64") as usize
+    + cfg!(feature = "value-u128") as usize
+    + cfg!(feature = "value-i128") as usize;
+#[allow(clippy::absurd_extreme_comparisons)]
+const _: () = assert!(SELECTED_VALUE_TYPES <= 1, "only one of the value-* features can be enabled");
+
 pub type MaxInstructionNumber = u16;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum InstructionName {
     LOAD,
     WRITE,
     READ,
     ADD,
+    SUB,
     MULTIPLY,
+    DIV,
+    MOD,
+    NEG,
+    AND,
+    OR,
+    XOR,
+    NOT,
+    SHL,
+    SHR,
     RETURN,
     EQUAL,
     GREATER,
@@ -15,4 +66,186 @@ pub enum InstructionName {
     DUP,
     POP,
     GOTO,
-}
\ No newline at end of file
+    JUMP,
+    JUMPIF,
+    JUMPIFNOT,
+    SPAWN,
+    SEND,
+    RECV,
+    CALL,
+    RET,
+}
+
+impl InstructionName {
+    /// Name of the instruction in source files.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            InstructionName::LOAD => "LOAD_VAL",
+            InstructionName::WRITE => "WRITE_VAR",
+            InstructionName::READ => "READ_VAR",
+            InstructionName::ADD => "ADD",
+            InstructionName::SUB => "SUB",
+            InstructionName::MULTIPLY => "MULTIPLY",
+            InstructionName::DIV => "DIV",
+            InstructionName::MOD => "MOD",
+            InstructionName::NEG => "NEG",
+            InstructionName::AND => "AND",
+            InstructionName::OR => "OR",
+            InstructionName::XOR => "XOR",
+            InstructionName::NOT => "NOT",
+            InstructionName::SHL => "SHL",
+            InstructionName::SHR => "SHR",
+            InstructionName::RETURN => "RETURN_VALUE",
+            InstructionName::EQUAL => "EQUAL",
+            InstructionName::GREATER => "GREATER",
+            InstructionName::LESS => "LESS",
+            InstructionName::DUP => "DUP",
+            InstructionName::POP => "POP",
+            InstructionName::GOTO => "GOTO",
+            InstructionName::JUMP => "JUMP",
+            InstructionName::JUMPIF => "JUMP_IF_TRUE",
+            InstructionName::JUMPIFNOT => "JUMP_IF_FALSE",
+            InstructionName::SPAWN => "SPAWN",
+            InstructionName::SEND => "SEND_CHANNEL",
+            InstructionName::RECV => "RECV_CHANNEL",
+            InstructionName::CALL => "CALL",
+            InstructionName::RET => "RET",
+        }
+    }
+}
+
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1024;
+pub const DEFAULT_MAX_STACK_DEPTH: usize = 65536;
+pub const DEFAULT_MAX_VARIABLES: usize = 4096;
+pub const DEFAULT_MAX_TASKS: usize = 1024;
+pub const DEFAULT_MAX_QUEUED_VALUES: usize = 65536;
+
+/// What arithmetic instructions do when the result doesn't fit into `VariableValue`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowMode {
+    /// Stop the program with a runtime error.
+    #[default]
+    Checked,
+    /// Wrap around at the boundary of the type.
+    Wrapping,
+    /// Clamp the result to the minimum or maximum value of the type.
+    Saturating,
+}
+
+impl FromStr for OverflowMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(OverflowMode::Checked),
+            "wrapping" => Ok(OverflowMode::Wrapping),
+            "saturating" => Ok(OverflowMode::Saturating),
+            _other => Err(format!("Unknown overflow mode: {}", s)),
+        }
+    }
+}
+
+pub const DEFAULT_GAS_COST: u64 = 1;
+
+/// Gas charged for executing an instruction, by instruction name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasCosts {
+    costs: HashMap<InstructionName, u64>,
+    default_cost: u64,
+}
+
+impl GasCosts {
+    /// Every instruction costs `default_cost` until overridden with `set_cost`.
+    pub fn new(default_cost: u64) -> Self {
+        GasCosts {
+            costs: HashMap::new(),
+            default_cost,
+        }
+    }
+
+    pub fn set_cost(&mut self, instruction_name: InstructionName, cost: u64) -> &mut Self {
+        self.costs.insert(instruction_name, cost);
+        self
+    }
+
+    pub fn cost(&self, instruction_name: InstructionName) -> u64 {
+        *self.costs.get(&instruction_name).unwrap_or(&self.default_cost)
+    }
+}
+
+impl Default for GasCosts {
+    /// Multiplication, division and spawning a task cost more than the other instructions.
+    fn default() -> Self {
+        let mut gas_costs = GasCosts::new(DEFAULT_GAS_COST);
+        gas_costs
+            .set_cost(InstructionName::MULTIPLY, 3)
+            .set_cost(InstructionName::DIV, 3)
+            .set_cost(InstructionName::MOD, 3)
+            .set_cost(InstructionName::SPAWN, 10);
+        gas_costs
+    }
+}
+
+/// Settings of a single interpreter run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpreterConfig {
+    /// Maximum number of nested `CALL`s that haven't returned yet, per task.
+    pub max_call_depth: usize,
+    /// Maximum number of values on the stack, per task.
+    pub max_stack_depth: usize,
+    /// Maximum number of distinct variables written in one function call, per task. Every frame of the call stack
+    /// has its own limit, so a task holds at most `max_variables` for each of its `max_call_depth + 1` frames.
+    pub max_variables: usize,
+    /// Maximum number of tasks that haven't finished yet, the main task included.
+    pub max_tasks: usize,
+    /// Maximum number of values sent and not received yet, across all channels.
+    pub max_queued_values: usize,
+    pub overflow_mode: OverflowMode,
+    /// Maximum number of instructions executed by all tasks together, unlimited when `None`.
+    pub max_instructions: Option<u64>,
+    /// Maximum gas all tasks together can spend, unlimited when `None`.
+    pub gas_limit: Option<u64>,
+    pub gas_costs: GasCosts,
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> Self {
+        InterpreterConfig {
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_stack_depth: DEFAULT_MAX_STACK_DEPTH,
+            max_variables: DEFAULT_MAX_VARIABLES,
+            max_tasks: DEFAULT_MAX_TASKS,
+            max_queued_values: DEFAULT_MAX_QUEUED_VALUES,
+            overflow_mode: OverflowMode::default(),
+            max_instructions: None,
+            gas_limit: None,
+            gas_costs: GasCosts::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instruction_mnemonics() {
+        assert_eq!(InstructionName::LOAD.mnemonic(), "LOAD_VAL");
+        assert_eq!(InstructionName::JUMPIFNOT.mnemonic(), "JUMP_IF_FALSE");
+    }
+
+    #[test]
+    fn default_gas_costs() {
+        let gas_costs = GasCosts::default();
+        assert_eq!(gas_costs.cost(InstructionName::POP), DEFAULT_GAS_COST);
+        assert_eq!(gas_costs.cost(InstructionName::MULTIPLY), 3);
+    }
+
+    #[test]
+    fn custom_gas_costs() {
+        let mut gas_costs = GasCosts::new(0);
+        gas_costs.set_cost(InstructionName::ADD, 7);
+        assert_eq!(gas_costs.cost(InstructionName::ADD), 7);
+        assert_eq!(gas_costs.cost(InstructionName::MULTIPLY), 0);
+    }
+}
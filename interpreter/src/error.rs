@@ -0,0 +1,519 @@
+use std::error;
+use std::fmt;
+use std::num::ParseIntError;
+use crate::bytecode::{SourcePosition, MAX_INSTRUCTIONS};
+use crate::config::{InstructionName, VariableValue, SIGNED_VALUES};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseErrorKind {
+    OpenFile(String),
+    ReadLine(String),
+    EmptyProgram,
+    UnknownInstruction(String),
+    ArgumentCount { expected: usize, got: usize },
+    /// Negative `LOAD_VAL` literal with an unsigned value type.
+    NegativeValue(String),
+    InvalidValue(ParseIntError),
+    /// `LOAD_VAL` operand that isn't a number token.
+    InvalidNumber(String),
+    InvalidVariableName(String),
+    InvalidLabelName(String),
+    InvalidChannelName(String),
+    DuplicatedLabel(String),
+    UndefinedLabel(String),
+    /// Token after a label on the same line.
+    UnexpectedToken(String),
+    /// The program has more instructions than label targets can address.
+    TooManyInstructions(usize),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    /// Line of the source file, counting from 1, `None` when the error isn't tied to a line.
+    pub line: Option<usize>,
+    /// Column of the offending token in the line, counting characters from 1.
+    pub column: Option<usize>,
+    pub instruction: Option<InstructionName>,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind) -> Self {
+        ParseError {
+            line: None,
+            column: None,
+            instruction: None,
+            kind,
+        }
+    }
+
+    pub fn for_instruction(instruction: InstructionName, kind: ParseErrorKind) -> Self {
+        ParseError {
+            line: None,
+            column: None,
+            instruction: Some(instruction),
+            kind,
+        }
+    }
+
+    pub fn at_line(self, line: usize) -> Self {
+        ParseError {
+            line: Some(line),
+            ..self
+        }
+    }
+
+    pub fn at_column(self, column: usize) -> Self {
+        ParseError {
+            column: Some(column),
+            ..self
+        }
+    }
+}
+
+/// Name of the instruction as used in "Error creating ... instruction" messages.
+fn instruction_description(instruction: Option<InstructionName>) -> String {
+    match instruction {
+        Some(InstructionName::JUMPIF) => "jump if true".to_string(),
+        Some(InstructionName::JUMPIFNOT) => "jump if false".to_string(),
+        Some(name) => format!("{:?}", name).to_lowercase(),
+        None => "unknown".to_string(),
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let ParseErrorKind::ReadLine(e) = &self.kind {
+            return write!(f, "Error reading line #{}: {}", self.line.unwrap_or_default(), e);
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "Unable to parse line #{}, column {}: ", line, column)?,
+            (Some(line), None) => write!(f, "Unable to parse line #{}: ", line)?,
+            _ => {}
+        }
+        let instruction = instruction_description(self.instruction);
+        match &self.kind {
+            ParseErrorKind::OpenFile(e) => write!(f, "Unable to open file: {}", e),
+            ParseErrorKind::ReadLine(_) => unreachable!(),
+            ParseErrorKind::EmptyProgram => write!(f, "Empty program"),
+            ParseErrorKind::UnknownInstruction(name) => write!(f, "Unknown instruction: {}", name),
+            ParseErrorKind::ArgumentCount { expected, got } => write!(
+                f,
+                "Error creating {} instruction: expected {} argument, got {}",
+                instruction, expected, got
+            ),
+            ParseErrorKind::NegativeValue(literal) => write!(
+                f,
+                "Error creating {} instruction: negative value {} requires a signed value type",
+                instruction, literal
+            ),
+            ParseErrorKind::InvalidValue(e) => write!(f, "Error creating {} instruction: {}", instruction, e),
+            ParseErrorKind::InvalidNumber(literal) => {
+                write!(f, "Error creating {} instruction: {} is not a number", instruction, literal)
+            }
+            ParseErrorKind::InvalidVariableName(name) => write!(f, "Invalid variable name {}", name),
+            ParseErrorKind::InvalidLabelName(name) => write!(f, "Invalid label name: {}", name),
+            ParseErrorKind::InvalidChannelName(name) => write!(f, "Invalid channel name {}", name),
+            ParseErrorKind::DuplicatedLabel(name) => write!(f, "duplicated label: {}", name),
+            ParseErrorKind::UndefinedLabel(name) => write!(f, "undefined label {}", name),
+            ParseErrorKind::UnexpectedToken(token) => write!(f, "Unexpected token after label: {}", token),
+            ParseErrorKind::TooManyInstructions(max) => write!(f, "Too many instructions, at most {} are allowed", max),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Every error found while parsing a program, in source order.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseErrors(pub Vec<ParseError>);
+
+impl ParseErrors {
+    pub fn errors(&self) -> &[ParseError] {
+        &self.0
+    }
+}
+
+impl From<ParseError> for ParseErrors {
+    fn from(error: ParseError) -> Self {
+        ParseErrors(vec![error])
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, error) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ParseErrors {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RuntimeErrorKind {
+    StackUnderflow,
+    UndefinedVariable(String),
+    Overflow,
+    DivisionByZero,
+    NegativeShift,
+    CallDepthExceeded(usize),
+    RetOutsideCall,
+    StackDepthExceeded(usize),
+    VariableLimitExceeded(usize),
+    TaskLimitExceeded(usize),
+    ChannelLimitExceeded(usize),
+    OutOfGas { used: u64, limit: u64, cost: u64 },
+    InstructionBudgetExhausted(u64),
+    /// Every remaining task waits on `RECV_CHANNEL` for a channel nobody sends to.
+    Deadlock(usize),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RuntimeError {
+    /// Index of the failing instruction, counting from 0, `None` for a deadlock.
+    pub instruction_index: Option<usize>,
+    pub instruction: Option<InstructionName>,
+    /// Where the failing instruction is in the source file, if the program was parsed from text.
+    pub position: Option<SourcePosition>,
+    pub kind: RuntimeErrorKind,
+}
+
+impl RuntimeError {
+    pub fn new(instruction_index: usize, kind: RuntimeErrorKind) -> Self {
+        RuntimeError {
+            instruction_index: Some(instruction_index),
+            instruction: None,
+            position: None,
+            kind,
+        }
+    }
+
+    /// Sets the instruction name unless the error already has one.
+    pub fn with_instruction(self, instruction: InstructionName) -> Self {
+        RuntimeError {
+            instruction: self.instruction.or(Some(instruction)),
+            ..self
+        }
+    }
+
+    pub fn at_position(self, position: Option<SourcePosition>) -> Self {
+        RuntimeError { position, ..self }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut instruction_number = (self.instruction_index.unwrap_or_default() + 1).to_string();
+        if let Some(position) = self.position {
+            instruction_number = format!("{} (line {}, column {})", instruction_number, position.line, position.column);
+        }
+        match &self.kind {
+            RuntimeErrorKind::UndefinedVariable(name) => {
+                return write!(
+                    f,
+                    "Runtime error: unable to get variable {}: doesn't exist, instruction#{}",
+                    name, instruction_number
+                )
+            }
+            RuntimeErrorKind::InstructionBudgetExhausted(steps) => {
+                return write!(
+                    f,
+                    "Runtime error: instruction budget exhausted after {} steps, next instruction #{}",
+                    steps, instruction_number
+                )
+            }
+            RuntimeErrorKind::Deadlock(tasks) => {
+                return write!(f, "Runtime error: deadlock: all {} tasks are blocked on RECV_CHANNEL", tasks)
+            }
+            _ => {}
+        }
+        write!(f, "Runtime error: unable to process instruction #{}: ", instruction_number)?;
+        let instruction = self
+            .instruction
+            .map(|name| format!("{:?}", name))
+            .unwrap_or_default();
+        match &self.kind {
+            RuntimeErrorKind::StackUnderflow => write!(f, "no value on stack"),
+            RuntimeErrorKind::Overflow => write!(f, "{} overflow", instruction),
+            RuntimeErrorKind::DivisionByZero => write!(f, "{} division by zero", instruction),
+            RuntimeErrorKind::NegativeShift => write!(f, "{} negative shift amount", instruction),
+            RuntimeErrorKind::CallDepthExceeded(max) => write!(f, "maximum call depth of {} exceeded", max),
+            RuntimeErrorKind::RetOutsideCall => write!(f, "RET outside of a function call"),
+            RuntimeErrorKind::StackDepthExceeded(max) => write!(f, "maximum stack depth of {} exceeded", max),
+            RuntimeErrorKind::VariableLimitExceeded(max) => write!(f, "maximum number of {} variables exceeded", max),
+            RuntimeErrorKind::TaskLimitExceeded(max) => write!(f, "maximum number of {} tasks exceeded", max),
+            RuntimeErrorKind::ChannelLimitExceeded(max) => {
+                write!(f, "maximum number of {} queued channel values exceeded", max)
+            }
+            RuntimeErrorKind::OutOfGas { used, limit, cost } => write!(
+                f,
+                "out of gas, {} of {} used, {} costs {}",
+                used, limit, instruction, cost
+            ),
+            RuntimeErrorKind::UndefinedVariable(_)
+            | RuntimeErrorKind::InstructionBudgetExhausted(_)
+            | RuntimeErrorKind::Deadlock(_) => unreachable!(),
+        }
+    }
+}
+
+impl error::Error for RuntimeError {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VerifyErrorKind {
+    /// The instruction pops more values than the stack has on some path.
+    StackUnderflow { needed: usize, available: i64 },
+    /// The instruction is reached with different stack depths from different paths.
+    InconsistentDepth(i64, i64),
+    /// The function returns with different stack depths from different `RET`s.
+    InconsistentReturn(i64, i64),
+    RetOutsideCall,
+    /// Every recursive call of the function changes the stack depth.
+    UnboundedRecursion,
+    /// The function is reached through more nested `CALL`s than the verifier follows.
+    CallsTooDeep(usize),
+}
+
+/// Error found by checking a program before running it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct VerifyError {
+    /// Index of the instruction, counting from 0.
+    pub instruction_index: usize,
+    pub instruction: InstructionName,
+    pub position: Option<SourcePosition>,
+    pub kind: VerifyErrorKind,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Verification error: instruction #{}", self.instruction_index + 1)?;
+        if let Some(position) = self.position {
+            write!(f, " (line {}, column {})", position.line, position.column)?;
+        }
+        write!(f, ": ")?;
+        match &self.kind {
+            VerifyErrorKind::StackUnderflow { needed, available } => write!(
+                f,
+                "{:?} needs {} values on the stack, there can be {}",
+                self.instruction, needed, available
+            ),
+            VerifyErrorKind::InconsistentDepth(depth1, depth2) => write!(
+                f,
+                "reached with {} and {} values on the stack from different paths",
+                depth1, depth2
+            ),
+            VerifyErrorKind::InconsistentReturn(depth1, depth2) => write!(
+                f,
+                "function returns with {} and {} values on the stack from different RETs",
+                depth1, depth2
+            ),
+            VerifyErrorKind::RetOutsideCall => write!(f, "RET outside of a function call"),
+            VerifyErrorKind::UnboundedRecursion => write!(f, "recursive CALLs change the stack depth without a bound"),
+            VerifyErrorKind::CallsTooDeep(max) => write!(f, "more than {} nested CALLs", max),
+        }
+    }
+}
+
+impl error::Error for VerifyError {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LoadErrorKind {
+    OpenFile(String),
+    /// The file doesn't start with the magic number of binary ByteCode.
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    /// The file stores values of a different width or signedness than `VariableValue`.
+    ValueTypeMismatch { width: u8, signed: bool },
+    ChecksumMismatch { expected: u32, actual: u32 },
+    UnexpectedEnd,
+    TrailingBytes(usize),
+    /// A name that isn't valid UTF-8, or a variable or channel name that isn't an identifier.
+    InvalidName(String),
+    DuplicatedLabel(String),
+    UnknownOpcode(u8),
+    /// An index past the end of the name or constant table.
+    InvalidIndex(u32),
+    InvalidLabelName(String),
+    InvalidLabelTarget(String),
+    UndefinedLabel(String),
+    /// The number of source positions is neither 0 nor the number of instructions.
+    InvalidPositions(u32),
+    EmptyProgram,
+    /// More instructions than label targets can address.
+    TooManyInstructions(u32),
+}
+
+/// Error of loading a program from the binary format.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LoadError {
+    /// Offset of the invalid data in the file, `None` when the error isn't tied to an offset.
+    pub offset: Option<usize>,
+    pub kind: LoadErrorKind,
+}
+
+impl LoadError {
+    pub fn new(kind: LoadErrorKind) -> Self {
+        LoadError { offset: None, kind }
+    }
+
+    pub fn at_offset(self, offset: usize) -> Self {
+        LoadError {
+            offset: Some(offset),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unable to load bytecode")?;
+        if let Some(offset) = self.offset {
+            write!(f, " at byte {}", offset)?;
+        }
+        write!(f, ": ")?;
+        match &self.kind {
+            LoadErrorKind::OpenFile(e) => write!(f, "unable to open file: {}", e),
+            LoadErrorKind::InvalidMagic => write!(f, "not a binary ByteCode file"),
+            LoadErrorKind::UnsupportedVersion(version) => write!(f, "unsupported format version {}", version),
+            LoadErrorKind::ValueTypeMismatch { width, signed } => write!(
+                f,
+                "values are {} {}-bit integers, expected {} {}-bit integers",
+                if *signed { "signed" } else { "unsigned" },
+                u32::from(*width) * 8,
+                if SIGNED_VALUES { "signed" } else { "unsigned" },
+                VariableValue::BITS
+            ),
+            LoadErrorKind::ChecksumMismatch { expected, actual } => {
+                write!(f, "checksum mismatch, expected {:#010x}, got {:#010x}", expected, actual)
+            }
+            LoadErrorKind::UnexpectedEnd => write!(f, "unexpected end of file"),
+            LoadErrorKind::TrailingBytes(count) => write!(f, "{} bytes after the end of the program", count),
+            LoadErrorKind::InvalidName(name) => write!(f, "invalid name {}", name),
+            LoadErrorKind::DuplicatedLabel(name) => write!(f, "duplicated label {}", name),
+            LoadErrorKind::UnknownOpcode(opcode) => write!(f, "unknown opcode {}", opcode),
+            LoadErrorKind::InvalidIndex(index) => write!(f, "table index {} is out of range", index),
+            LoadErrorKind::InvalidLabelName(name) => write!(f, "invalid label name {}", name),
+            LoadErrorKind::InvalidLabelTarget(name) => write!(f, "label {} points past the end of the program", name),
+            LoadErrorKind::UndefinedLabel(name) => write!(f, "undefined label {}", name),
+            LoadErrorKind::InvalidPositions(count) => {
+                write!(f, "{} source positions don't match the instructions", count)
+            }
+            LoadErrorKind::EmptyProgram => write!(f, "empty program"),
+            LoadErrorKind::TooManyInstructions(count) => write!(
+                f,
+                "{} instructions, at most {} are allowed",
+                count,
+                MAX_INSTRUCTIONS
+            ),
+        }
+    }
+}
+
+impl error::Error for LoadError {}
+
+/// Error of parsing and running a program from a file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Error {
+    Parse(ParseErrors),
+    Runtime(RuntimeError),
+}
+
+impl From<ParseErrors> for Error {
+    fn from(error: ParseErrors) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Self {
+        Error::Runtime(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(error) => write!(f, "{}", error),
+            Error::Runtime(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Parse(error) => Some(error),
+            Error::Runtime(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_parse_error() {
+        let error = ParseError::for_instruction(
+            InstructionName::JUMPIF,
+            ParseErrorKind::ArgumentCount { expected: 1, got: 0 },
+        );
+        assert_eq!(
+            error.to_string(),
+            "Error creating jump if true instruction: expected 1 argument, got 0"
+        );
+        assert_eq!(
+            error.at_line(3).to_string(),
+            "Unable to parse line #3: Error creating jump if true instruction: expected 1 argument, got 0"
+        );
+    }
+
+    #[test]
+    fn display_parse_errors() {
+        let errors = ParseErrors(vec![
+            ParseError::new(ParseErrorKind::UndefinedLabel(".a".to_string())).at_line(1),
+            ParseError::new(ParseErrorKind::UndefinedLabel(".b".to_string())).at_line(4),
+        ]);
+        assert_eq!(
+            errors.to_string(),
+            "Unable to parse line #1: undefined label .a\nUnable to parse line #4: undefined label .b"
+        );
+    }
+
+    #[test]
+    fn display_runtime_error() {
+        let error = RuntimeError::new(2, RuntimeErrorKind::Overflow).with_instruction(InstructionName::ADD);
+        assert_eq!(error.to_string(), "Runtime error: unable to process instruction #3: ADD overflow");
+
+        let error = error.at_position(Some(SourcePosition { line: 5, column: 9 }));
+        assert_eq!(
+            error.to_string(),
+            "Runtime error: unable to process instruction #3 (line 5, column 9): ADD overflow"
+        );
+
+        let error = RuntimeError {
+            instruction_index: None,
+            instruction: None,
+            position: None,
+            kind: RuntimeErrorKind::Deadlock(2),
+        };
+        assert_eq!(error.to_string(), "Runtime error: deadlock: all 2 tasks are blocked on RECV_CHANNEL");
+    }
+
+    #[test]
+    fn display_load_error() {
+        let error = LoadError::new(LoadErrorKind::UnknownOpcode(200)).at_offset(30);
+        assert_eq!(error.to_string(), "Unable to load bytecode at byte 30: unknown opcode 200");
+
+        let error = LoadError::new(LoadErrorKind::ChecksumMismatch { expected: 0x12ab, actual: 0xff });
+        assert_eq!(
+            error.to_string(),
+            "Unable to load bytecode: checksum mismatch, expected 0x000012ab, got 0x000000ff"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use crate::config::{OverflowMode, VariableValue};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ArithmeticError {
+    Overflow,
+    DivisionByZero,
+    NegativeShift,
+}
+
+pub type ArithmeticResult = Result<VariableValue, ArithmeticError>;
+pub type ArithmeticOperation = fn(OverflowMode, VariableValue, VariableValue) -> ArithmeticResult;
+
+/// Fails with `ArithmeticError::Overflow` only in `OverflowMode::Checked`.
+pub fn add(mode: OverflowMode, value1: VariableValue, value2: VariableValue) -> ArithmeticResult {
+    match mode {
+        OverflowMode::Checked => value1.checked_add(value2).ok_or(ArithmeticError::Overflow),
+        OverflowMode::Wrapping => Ok(value1.wrapping_add(value2)),
+        OverflowMode::Saturating => Ok(value1.saturating_add(value2)),
+    }
+}
+
+/// Computes `value1 - value2`, fails with `ArithmeticError::Overflow` only in `OverflowMode::Checked`.
+pub fn sub(mode: OverflowMode, value1: VariableValue, value2: VariableValue) -> ArithmeticResult {
+    match mode {
+        OverflowMode::Checked => value1.checked_sub(value2).ok_or(ArithmeticError::Overflow),
+        OverflowMode::Wrapping => Ok(value1.wrapping_sub(value2)),
+        OverflowMode::Saturating => Ok(value1.saturating_sub(value2)),
+    }
+}
+
+/// Fails with `ArithmeticError::Overflow` only in `OverflowMode::Checked`.
+pub fn multiply(mode: OverflowMode, value1: VariableValue, value2: VariableValue) -> ArithmeticResult {
+    match mode {
+        OverflowMode::Checked => value1.checked_mul(value2).ok_or(ArithmeticError::Overflow),
+        OverflowMode::Wrapping => Ok(value1.wrapping_mul(value2)),
+        OverflowMode::Saturating => Ok(value1.saturating_mul(value2)),
+    }
+}
+
+/// Computes `value1 / value2` rounding towards zero, division by zero fails in every mode.
+pub fn div(mode: OverflowMode, value1: VariableValue, value2: VariableValue) -> ArithmeticResult {
+    if value2 == 0 {
+        return Err(ArithmeticError::DivisionByZero);
+    }
+    match mode {
+        OverflowMode::Checked => value1.checked_div(value2).ok_or(ArithmeticError::Overflow),
+        OverflowMode::Wrapping => Ok(value1.wrapping_div(value2)),
+        OverflowMode::Saturating => Ok(value1.saturating_div(value2)),
+    }
+}
+
+/// Computes `value1 % value2` with the sign of `value1`, division by zero fails in every mode.
+pub fn modulo(mode: OverflowMode, value1: VariableValue, value2: VariableValue) -> ArithmeticResult {
+    if value2 == 0 {
+        return Err(ArithmeticError::DivisionByZero);
+    }
+    match mode {
+        OverflowMode::Checked => value1.checked_rem(value2).ok_or(ArithmeticError::Overflow),
+        // `MIN % -1` is mathematically 0, so there is nothing to saturate.
+        OverflowMode::Wrapping | OverflowMode::Saturating => Ok(value1.wrapping_rem(value2)),
+    }
+}
+
+/// Negation overflows for `MIN` of signed types and for any non-zero value of unsigned types.
+pub fn neg(mode: OverflowMode, value: VariableValue) -> ArithmeticResult {
+    match mode {
+        OverflowMode::Checked => value.checked_neg().ok_or(ArithmeticError::Overflow),
+        OverflowMode::Wrapping => Ok(value.wrapping_neg()),
+        OverflowMode::Saturating => Ok(value.checked_neg().unwrap_or(
+            if value > 0 { VariableValue::MIN } else { VariableValue::MAX }
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SIGNED_VALUES;
+
+    const MODES: [OverflowMode; 3] = [OverflowMode::Checked, OverflowMode::Wrapping, OverflowMode::Saturating];
+
+    #[test]
+    fn test_add_overflow() {
+        let max = VariableValue::MAX;
+        assert_eq!(add(OverflowMode::Checked, max, 1), Err(ArithmeticError::Overflow));
+        assert_eq!(add(OverflowMode::Wrapping, max, 1), Ok(VariableValue::MIN));
+        assert_eq!(add(OverflowMode::Saturating, max, 1), Ok(max));
+    }
+
+    #[test]
+    fn test_sub_overflow() {
+        let min = VariableValue::MIN;
+        assert_eq!(sub(OverflowMode::Checked, min, 1), Err(ArithmeticError::Overflow));
+        assert_eq!(sub(OverflowMode::Wrapping, min, 1), Ok(VariableValue::MAX));
+        assert_eq!(sub(OverflowMode::Saturating, min, 1), Ok(min));
+    }
+
+    #[test]
+    fn test_multiply_overflow() {
+        let max = VariableValue::MAX;
+        assert_eq!(multiply(OverflowMode::Checked, max, 2), Err(ArithmeticError::Overflow));
+        assert_eq!(multiply(OverflowMode::Wrapping, max, 2), Ok(max.wrapping_mul(2)));
+        assert_eq!(multiply(OverflowMode::Saturating, max, 2), Ok(max));
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        for mode in MODES {
+            assert_eq!(div(mode, 10, 0), Err(ArithmeticError::DivisionByZero));
+            assert_eq!(modulo(mode, 10, 0), Err(ArithmeticError::DivisionByZero));
+        }
+    }
+
+    #[test]
+    fn test_neg_overflow() {
+        let min = VariableValue::MIN;
+        if SIGNED_VALUES {
+            assert_eq!(neg(OverflowMode::Checked, min), Err(ArithmeticError::Overflow));
+            assert_eq!(neg(OverflowMode::Wrapping, min), Ok(min));
+            assert_eq!(neg(OverflowMode::Saturating, min), Ok(VariableValue::MAX));
+            assert_eq!(neg(OverflowMode::Checked, 5).and_then(|value| neg(OverflowMode::Checked, value)), Ok(5));
+        } else {
+            assert_eq!(neg(OverflowMode::Checked, 5), Err(ArithmeticError::Overflow));
+            assert_eq!(neg(OverflowMode::Saturating, 5), Ok(0));
+            assert_eq!(neg(OverflowMode::Wrapping, 1), Ok(VariableValue::MAX));
+        }
+    }
+
+    #[test]
+    fn test_no_overflow() {
+        for mode in MODES {
+            assert_eq!(add(mode, 10, 20), Ok(30));
+            assert_eq!(sub(mode, 20, 10), Ok(10));
+            assert_eq!(multiply(mode, 10, 20), Ok(200));
+            assert_eq!(div(mode, 20, 6), Ok(3));
+            assert_eq!(modulo(mode, 20, 6), Ok(2));
+            assert_eq!(neg(mode, 0), Ok(0));
+        }
+    }
+}
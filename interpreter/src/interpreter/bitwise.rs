@@ -0,0 +1,94 @@
+use super::arithmetic::{ArithmeticError, ArithmeticResult};
+use crate::config::VariableValue;
+
+pub type BitwiseOperation = fn(VariableValue, VariableValue) -> VariableValue;
+pub type ShiftOperation = fn(VariableValue, VariableValue) -> ArithmeticResult;
+
+pub fn and(value1: VariableValue, value2: VariableValue) -> VariableValue {
+    value1 & value2
+}
+
+pub fn or(value1: VariableValue, value2: VariableValue) -> VariableValue {
+    value1 | value2
+}
+
+pub fn xor(value1: VariableValue, value2: VariableValue) -> VariableValue {
+    value1 ^ value2
+}
+
+pub fn not(value: VariableValue) -> VariableValue {
+    !value
+}
+
+/// Shifts `value1` left by `value2` bits, shifting by the type width or more gives 0.
+pub fn shl(value1: VariableValue, value2: VariableValue) -> ArithmeticResult {
+    Ok(match shift_amount(value2)? {
+        Some(amount) => value1 << amount,
+        None => 0,
+    })
+}
+
+/// Shifts `value1` right by `value2` bits, arithmetic for signed types and logical for unsigned ones.
+/// Shifting by the type width or more leaves only the sign bits.
+pub fn shr(value1: VariableValue, value2: VariableValue) -> ArithmeticResult {
+    Ok(match shift_amount(value2)? {
+        Some(amount) => value1 >> amount,
+        None if is_negative(value1) => !0,
+        None => 0,
+    })
+}
+
+/// Returns `None` when the amount doesn't fit into the type width.
+// The conversion can't fail for the default `VariableValue`, but can for the wider ones.
+#[allow(clippy::unnecessary_fallible_conversions)]
+fn shift_amount(value: VariableValue) -> Result<Option<u32>, ArithmeticError> {
+    if is_negative(value) {
+        return Err(ArithmeticError::NegativeShift);
+    }
+    Ok(u32::try_from(value).ok().filter(|amount| *amount < VariableValue::BITS))
+}
+
+// The comparison is useless for the default unsigned `VariableValue`, but not for the signed ones.
+#[allow(unused_comparisons, clippy::absurd_extreme_comparisons)]
+fn is_negative(value: VariableValue) -> bool {
+    value < 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SIGNED_VALUES;
+
+    #[test]
+    fn test_logical_operations() {
+        assert_eq!(and(0b1100, 0b1010), 0b1000);
+        assert_eq!(or(0b1100, 0b1010), 0b1110);
+        assert_eq!(xor(0b1100, 0b1010), 0b0110);
+        assert_eq!(not(0), !0);
+    }
+
+    #[test]
+    fn test_shifts() {
+        assert_eq!(shl(0b11, 2), Ok(0b1100));
+        assert_eq!(shr(0b1100, 2), Ok(0b11));
+    }
+
+    #[test]
+    fn test_shifts_by_type_width() {
+        let width = VariableValue::BITS as VariableValue;
+        assert_eq!(shl(1, width), Ok(0));
+        assert_eq!(shl(1, VariableValue::MAX), Ok(0));
+        assert_eq!(shr(VariableValue::MAX, width), Ok(0));
+        if SIGNED_VALUES {
+            assert_eq!(shr(VariableValue::MIN, width), Ok(!0));
+        }
+    }
+
+    #[test]
+    fn test_shift_by_negative_amount() {
+        if SIGNED_VALUES {
+            assert_eq!(shl(1, !0), Err(ArithmeticError::NegativeShift));
+            assert_eq!(shr(1, !0), Err(ArithmeticError::NegativeShift));
+        }
+    }
+}
@@ -1,22 +1,40 @@
-use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::mem;
+use std::rc::Rc;
+use super::arithmetic::{self, ArithmeticError, ArithmeticOperation};
+use super::bitwise::{self, BitwiseOperation, ShiftOperation};
 use super::stack::Stack;
-use crate::bytecode::{instruction::Instruction, ByteCode};
-use crate::config::{InstructionName, VariableValue};
+use crate::bytecode::opcode::{self, OpCode, Program};
+use crate::bytecode::ByteCode;
+use crate::config::{InstructionName, InterpreterConfig, VariableValue};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+
+/// Caller's locals saved by `CALL` and restored by `RET`.
+struct Frame {
+    vars: Vec<Option<VariableValue>>,
+    defined_vars: usize,
+    return_address: usize,
+}
 
 struct InterpreterState {
     stack: Stack<VariableValue>,
-    vars: HashMap<String, VariableValue>,
+    /// Variables of the current frame indexed by slot, grows on the first write to a slot.
+    vars: Vec<Option<VariableValue>>,
+    /// Number of slots of `vars` holding a value.
+    defined_vars: usize,
+    call_stack: Stack<Frame>,
     instruction_pointer: usize,
 }
 
 impl InterpreterState {
     pub fn new() -> Self {
-        let ret = InterpreterState {
+        InterpreterState {
             stack: Stack::new(),
-            vars: HashMap::new(),
+            vars: Vec::new(),
+            defined_vars: 0,
+            call_stack: Stack::new(),
             instruction_pointer: 0,
-        };
-        ret
+        }
     }
     pub fn get_instruction_pointer(&self) -> usize {
         self.instruction_pointer
@@ -27,92 +45,289 @@ impl InterpreterState {
     pub fn next(&mut self) {
         self.instruction_pointer += 1;
     }
-    pub fn pop_value(&mut self) -> Result<VariableValue, String> {
+    pub fn pop_value(&mut self) -> Result<VariableValue, RuntimeError> {
         match self.stack.pop() {
             Some(v) => Ok(v),
-            None => Err(format!(
-                "Runtime error: unable to process instruction #{}: no value on stack",
-                self.instruction_pointer + 1
-            )),
+            None => Err(RuntimeError::new(self.instruction_pointer, RuntimeErrorKind::StackUnderflow)),
         }
     }
     pub fn push_value(&mut self, v: VariableValue) {
         self.stack.push(v);
     }
-    pub fn add_var(&mut self, variable_name: &str, value: VariableValue) {
-        self.vars.insert(variable_name.to_string(), value);
+    pub fn add_var(&mut self, slot: usize, value: VariableValue, max_variables: usize) -> Result<(), RuntimeError> {
+        if self.read_var(slot).is_none() {
+            if self.defined_vars >= max_variables {
+                return Err(RuntimeError::new(
+                    self.instruction_pointer,
+                    RuntimeErrorKind::VariableLimitExceeded(max_variables),
+                ));
+            }
+            self.defined_vars += 1;
+        }
+        if slot >= self.vars.len() {
+            self.vars.resize(slot + 1, None);
+        }
+        self.vars[slot] = Some(value);
+        Ok(())
+    }
+    pub fn read_var(&self, slot: usize) -> Option<VariableValue> {
+        self.vars.get(slot).copied().flatten()
     }
-    pub fn read_var(&mut self, variable_name: &str) -> Result<VariableValue, String> {
-        if !self.vars.contains_key(variable_name) {
-            return Err(format!(
-                "Runtime error: unable to get variable: doesn't exist, instruction#{}",
-                self.instruction_pointer + 1
+    pub fn call(&mut self, address: usize, max_call_depth: usize) -> Result<(), RuntimeError> {
+        if self.call_stack.len() >= max_call_depth {
+            return Err(RuntimeError::new(
+                self.instruction_pointer,
+                RuntimeErrorKind::CallDepthExceeded(max_call_depth),
             ));
         }
-        return Ok(*self.vars.get(variable_name).unwrap());
+        self.call_stack.push(Frame {
+            vars: mem::take(&mut self.vars),
+            defined_vars: mem::take(&mut self.defined_vars),
+            return_address: self.instruction_pointer + 1,
+        });
+        self.instruction_pointer = address;
+        Ok(())
+    }
+    pub fn ret(&mut self) -> Result<(), RuntimeError> {
+        match self.call_stack.pop() {
+            Some(frame) => {
+                self.vars = frame.vars;
+                self.defined_vars = frame.defined_vars;
+                self.instruction_pointer = frame.return_address;
+                Ok(())
+            }
+            None => Err(RuntimeError::new(self.instruction_pointer, RuntimeErrorKind::RetOutsideCall)),
+        }
     }
 }
 
+/// Index of the task that starts at the first instruction; the program ends when it finishes.
+const MAIN_TASK: usize = 0;
+
+/// Outcome of a successful run.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ExecutionReport {
+    /// Value returned by the main task, `None` if it reached the end of the program.
+    pub value: Option<VariableValue>,
+    pub executed_instructions: u64,
+    pub gas_used: u64,
+}
+
+enum TaskStatus {
+    Running,
+    Blocked,
+    Finished(Option<VariableValue>),
+}
+
 pub struct Interpreter {
-    bytecode: ByteCode,
+    program: Rc<Program>,
+    /// Gas cost of every opcode of the program, by index.
+    gas_costs: Vec<u64>,
+    config: InterpreterConfig,
+    /// Queue of every channel indexed by slot, see `Program::channels`.
+    channels: Vec<VecDeque<VariableValue>>,
+    /// Values sent to any channel and not received yet.
+    queued_values: usize,
+    spawned_tasks: Vec<InterpreterState>,
+    /// Tasks that haven't finished yet, the main task included.
+    live_tasks: usize,
+    executed_instructions: u64,
+    gas_used: u64,
 }
 
 impl Interpreter {
     pub fn new(bytecode: ByteCode) -> Self {
-        Interpreter { bytecode }
+        Interpreter::with_config(bytecode, InterpreterConfig::default())
     }
-    pub fn interpret(&mut self) -> Result<Option<VariableValue>, String> {
-        let mut interpreter_state = InterpreterState::new();
-        loop {
-            let instruction = match self.bytecode.get_instruction(interpreter_state.get_instruction_pointer() as usize) {
-                Some(instruction) => instruction.clone(),
-                None => return Ok(None),
-            };
-            let instruction_name = match Some(instruction.name) {
-                Some(ref instruction_name) => instruction_name.clone(),
-                None => return Err("Invalid instruction: invalid name".to_string()),
-            };
 
-            match instruction_name {
-                InstructionName::LOAD => self.interpret_load_instruction(&mut interpreter_state, instruction),
-                InstructionName::WRITE => self.interpret_write_instruction(&mut interpreter_state, instruction),
-                InstructionName::READ => self.interpret_read_instruction(&mut interpreter_state, instruction),
-                InstructionName::ADD => self.interpret_add_instruction(&mut interpreter_state),
-                InstructionName::MULTIPLY => self.interpret_multiply_instruction(&mut interpreter_state),
-                InstructionName::GREATER => self.interpret_greater_instruction(&mut interpreter_state),
-                InstructionName::LESS => self.interpret_less_instruction(&mut interpreter_state),
-                InstructionName::EQUAL => self.interpret_equal_instruction(&mut interpreter_state),
-                InstructionName::DUP => self.interpret_dup_instruction(&mut interpreter_state),
-                InstructionName::POP => self.interpret_pop_instruction(&mut interpreter_state),
-                InstructionName::GOTO => self.interpret_goto_instruction(&mut interpreter_state, instruction),
-                InstructionName::RETURN => {
-                    return interpreter_state.pop_value().map(Some)
+    pub fn with_config(bytecode: ByteCode, config: InterpreterConfig) -> Self {
+        let program = opcode::compile(&bytecode);
+        let channels = vec![VecDeque::new(); program.channels.len()];
+        let gas_costs = program
+            .opcodes
+            .iter()
+            .map(|opcode| config.gas_costs.cost(opcode.name()))
+            .collect();
+        Interpreter {
+            program: Rc::new(program),
+            gas_costs,
+            config,
+            channels,
+            queued_values: 0,
+            spawned_tasks: Vec::new(),
+            live_tasks: 0,
+            executed_instructions: 0,
+            gas_used: 0,
+        }
+    }
+
+    pub fn interpret(&mut self) -> Result<Option<VariableValue>, RuntimeError> {
+        self.run().map(|report| report.value)
+    }
+
+    /// Gas spent by the last run so far, also valid after the run failed.
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    /// Runs all tasks round-robin, one instruction per task per round, until the main task finishes.
+    pub fn run(&mut self) -> Result<ExecutionReport, RuntimeError> {
+        for queue in &mut self.channels {
+            queue.clear();
+        }
+        self.queued_values = 0;
+        self.live_tasks = 1;
+        self.executed_instructions = 0;
+        self.gas_used = 0;
+        let program = Rc::clone(&self.program);
+        let mut tasks = vec![InterpreterState::new()];
+        loop {
+            let mut progressed = false;
+            let mut task_index = MAIN_TASK;
+            while task_index < tasks.len() {
+                let status = self.step(&program, &mut tasks[task_index]).map_err(|e| {
+                    let position = e.instruction_index.and_then(|index| program.positions.get(index).copied());
+                    e.at_position(position)
+                })?;
+                tasks.append(&mut self.spawned_tasks);
+                match status {
+                    TaskStatus::Running => progressed = true,
+                    TaskStatus::Blocked => {}
+                    TaskStatus::Finished(value) => {
+                        if task_index == MAIN_TASK {
+                            return Ok(ExecutionReport {
+                                value,
+                                executed_instructions: self.executed_instructions,
+                                gas_used: self.gas_used,
+                            });
+                        }
+                        tasks.remove(task_index);
+                        self.live_tasks -= 1;
+                        progressed = true;
+                        continue;
+                    }
                 }
-            }?;
+                task_index += 1;
+            }
+            if !progressed {
+                return Err(RuntimeError {
+                    instruction_index: None,
+                    instruction: None,
+                    position: None,
+                    kind: RuntimeErrorKind::Deadlock(tasks.len()),
+                });
+            }
         }
     }
 
-    fn interpret_load_instruction(&mut self, interpreter_state: &mut InterpreterState, instruction: Instruction) -> Result<(), String> {
-        interpreter_state.push_value(instruction.arg.unwrap());
+    fn step(&mut self, program: &Program, interpreter_state: &mut InterpreterState) -> Result<TaskStatus, RuntimeError> {
+        let instruction_pointer = interpreter_state.get_instruction_pointer();
+        let opcode = match program.opcodes.get(instruction_pointer) {
+            Some(opcode) => opcode,
+            None => return Ok(TaskStatus::Finished(None)),
+        };
+        if self.config.max_instructions == Some(self.executed_instructions) {
+            return Err(RuntimeError::new(
+                instruction_pointer,
+                RuntimeErrorKind::InstructionBudgetExhausted(self.executed_instructions),
+            )
+            .with_instruction(opcode.name()));
+        }
+        let gas_cost = self.gas_costs[instruction_pointer];
+        if let Some(gas_limit) = self.config.gas_limit {
+            // A sum that doesn't fit in `u64` is over every limit.
+            if self.gas_used.checked_add(gas_cost).is_none_or(|total| total > gas_limit) {
+                return Err(RuntimeError::new(
+                    instruction_pointer,
+                    RuntimeErrorKind::OutOfGas {
+                        used: self.gas_used,
+                        limit: gas_limit,
+                        cost: gas_cost,
+                    },
+                )
+                .with_instruction(opcode.name()));
+            }
+        }
+
+        let status = self
+            .execute(opcode, interpreter_state)
+            .map_err(|e| e.with_instruction(opcode.name()))?;
+        if interpreter_state.stack.len() > self.config.max_stack_depth {
+            return Err(RuntimeError::new(
+                instruction_pointer,
+                RuntimeErrorKind::StackDepthExceeded(self.config.max_stack_depth),
+            )
+            .with_instruction(opcode.name()));
+        }
+        if !matches!(status, TaskStatus::Blocked) {
+            self.executed_instructions += 1;
+            self.gas_used = self.gas_used.saturating_add(gas_cost);
+        }
+        Ok(status)
+    }
+
+    fn execute(&mut self, opcode: &OpCode, interpreter_state: &mut InterpreterState) -> Result<TaskStatus, RuntimeError> {
+        match opcode {
+            OpCode::Load(value) => self.interpret_load_instruction(interpreter_state, *value),
+            OpCode::Write(slot) => self.interpret_write_instruction(interpreter_state, *slot),
+            OpCode::Read(slot) => self.interpret_read_instruction(interpreter_state, *slot),
+            OpCode::Add => self.interpret_add_instruction(interpreter_state),
+            OpCode::Sub => self.interpret_sub_instruction(interpreter_state),
+            OpCode::Multiply => self.interpret_multiply_instruction(interpreter_state),
+            OpCode::Div => self.interpret_div_instruction(interpreter_state),
+            OpCode::Mod => self.interpret_mod_instruction(interpreter_state),
+            OpCode::Neg => self.interpret_neg_instruction(interpreter_state),
+            OpCode::And => self.interpret_bitwise_instruction(interpreter_state, bitwise::and),
+            OpCode::Or => self.interpret_bitwise_instruction(interpreter_state, bitwise::or),
+            OpCode::Xor => self.interpret_bitwise_instruction(interpreter_state, bitwise::xor),
+            OpCode::Not => self.interpret_not_instruction(interpreter_state),
+            OpCode::Shl => self.interpret_shift_instruction(interpreter_state, InstructionName::SHL, bitwise::shl),
+            OpCode::Shr => self.interpret_shift_instruction(interpreter_state, InstructionName::SHR, bitwise::shr),
+            OpCode::Greater => self.interpret_greater_instruction(interpreter_state),
+            OpCode::Less => self.interpret_less_instruction(interpreter_state),
+            OpCode::Equal => self.interpret_equal_instruction(interpreter_state),
+            OpCode::Dup => self.interpret_dup_instruction(interpreter_state),
+            OpCode::Pop => self.interpret_pop_instruction(interpreter_state),
+            OpCode::Goto(target) => self.interpret_conditional_jump_instruction(interpreter_state, *target, true),
+            OpCode::Jump(target) => self.interpret_jump_instruction(interpreter_state, *target),
+            OpCode::JumpIf(target) => self.interpret_conditional_jump_instruction(interpreter_state, *target, true),
+            OpCode::JumpIfNot(target) => self.interpret_conditional_jump_instruction(interpreter_state, *target, false),
+            OpCode::Spawn(target) => self.interpret_spawn_instruction(interpreter_state, *target),
+            OpCode::Send(channel) => self.interpret_send_instruction(interpreter_state, *channel),
+            OpCode::Call(target) => self.interpret_call_instruction(interpreter_state, *target),
+            OpCode::Ret => interpreter_state.ret(),
+            OpCode::Recv(channel) => return self.interpret_recv_instruction(interpreter_state, *channel),
+            OpCode::Return => {
+                return interpreter_state.pop_value().map(|value| TaskStatus::Finished(Some(value)))
+            }
+        }?;
+        Ok(TaskStatus::Running)
+    }
+
+    fn interpret_load_instruction(&mut self, interpreter_state: &mut InterpreterState, value: VariableValue) -> Result<(), RuntimeError> {
+        interpreter_state.push_value(value);
         interpreter_state.next();
         Ok(())
     }
 
-    fn interpret_write_instruction(&mut self, interpreter_state: &mut InterpreterState, instruction: Instruction) -> Result<(), String> {
+    fn interpret_write_instruction(&mut self, interpreter_state: &mut InterpreterState, slot: usize) -> Result<(), RuntimeError> {
         match interpreter_state.pop_value() {
-            Err(e) => return Err(e),
+            Err(e) => Err(e),
             Ok(value) => {
-                interpreter_state.add_var(&instruction.variable.unwrap(), value);
+                interpreter_state.add_var(slot, value, self.config.max_variables)?;
                 interpreter_state.next();
                 Ok(())
             }
         }
     }
 
-    fn interpret_read_instruction(&mut self, interpreter_state: &mut InterpreterState, instruction: Instruction) -> Result<(), String> {
-        match interpreter_state.read_var(&instruction.variable.unwrap()) {
-            Err(e) => return Err(e),
-            Ok(value) => {
+    fn interpret_read_instruction(&mut self, interpreter_state: &mut InterpreterState, slot: usize) -> Result<(), RuntimeError> {
+        match interpreter_state.read_var(slot) {
+            None => Err(RuntimeError::new(
+                interpreter_state.get_instruction_pointer(),
+                RuntimeErrorKind::UndefinedVariable(self.program.variables[slot].clone()),
+            )),
+            Some(value) => {
                 interpreter_state.push_value(value);
                 interpreter_state.next();
                 Ok(())
@@ -120,23 +335,97 @@ impl Interpreter {
         }
     }
 
-    fn interpret_add_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), String> {
+    fn interpret_add_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
+        self.interpret_arithmetic_instruction(interpreter_state, InstructionName::ADD, arithmetic::add)
+    }
+
+    fn interpret_sub_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
+        self.interpret_arithmetic_instruction(interpreter_state, InstructionName::SUB, arithmetic::sub)
+    }
+
+    fn interpret_multiply_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
+        self.interpret_arithmetic_instruction(interpreter_state, InstructionName::MULTIPLY, arithmetic::multiply)
+    }
+
+    fn interpret_div_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
+        self.interpret_arithmetic_instruction(interpreter_state, InstructionName::DIV, arithmetic::div)
+    }
+
+    fn interpret_mod_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
+        self.interpret_arithmetic_instruction(interpreter_state, InstructionName::MOD, arithmetic::modulo)
+    }
+
+    fn interpret_neg_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
+        let value = interpreter_state.pop_value()?;
+        match arithmetic::neg(self.config.overflow_mode, value) {
+            Ok(value) => interpreter_state.push_value(value),
+            Err(e) => return Err(Interpreter::arithmetic_error(interpreter_state, InstructionName::NEG, e)),
+        }
+        interpreter_state.next();
+        Ok(())
+    }
+
+    fn interpret_arithmetic_instruction(
+        &mut self,
+        interpreter_state: &mut InterpreterState,
+        instruction_name: InstructionName,
+        operation: ArithmeticOperation,
+    ) -> Result<(), RuntimeError> {
+        let value1 = interpreter_state.pop_value()?;
+        let value2= interpreter_state.pop_value()?;
+        match operation(self.config.overflow_mode, value1, value2) {
+            Ok(value) => interpreter_state.push_value(value),
+            Err(e) => return Err(Interpreter::arithmetic_error(interpreter_state, instruction_name, e)),
+        }
+        interpreter_state.next();
+        Ok(())
+    }
+
+    fn interpret_bitwise_instruction(&mut self, interpreter_state: &mut InterpreterState, operation: BitwiseOperation) -> Result<(), RuntimeError> {
         let value1 = interpreter_state.pop_value()?;
         let value2= interpreter_state.pop_value()?;
-        interpreter_state.push_value(value1 + value2);
+        interpreter_state.push_value(operation(value1, value2));
+        interpreter_state.next();
+        Ok(())
+    }
+
+    fn interpret_not_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
+        let value = interpreter_state.pop_value()?;
+        interpreter_state.push_value(bitwise::not(value));
         interpreter_state.next();
         Ok(())
     }
 
-    fn interpret_multiply_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), String> {
+    fn interpret_shift_instruction(
+        &mut self,
+        interpreter_state: &mut InterpreterState,
+        instruction_name: InstructionName,
+        operation: ShiftOperation,
+    ) -> Result<(), RuntimeError> {
         let value1 = interpreter_state.pop_value()?;
         let value2= interpreter_state.pop_value()?;
-        interpreter_state.push_value(value1 * value2);
+        match operation(value1, value2) {
+            Ok(value) => interpreter_state.push_value(value),
+            Err(e) => return Err(Interpreter::arithmetic_error(interpreter_state, instruction_name, e)),
+        }
         interpreter_state.next();
         Ok(())
     }
 
-    fn interpret_greater_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), String> {
+    fn arithmetic_error(
+        interpreter_state: &InterpreterState,
+        instruction_name: InstructionName,
+        error: ArithmeticError,
+    ) -> RuntimeError {
+        let kind = match error {
+            ArithmeticError::Overflow => RuntimeErrorKind::Overflow,
+            ArithmeticError::DivisionByZero => RuntimeErrorKind::DivisionByZero,
+            ArithmeticError::NegativeShift => RuntimeErrorKind::NegativeShift,
+        };
+        RuntimeError::new(interpreter_state.get_instruction_pointer(), kind).with_instruction(instruction_name)
+    }
+
+    fn interpret_greater_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
         let value1 = interpreter_state.pop_value()?;
         let value2= interpreter_state.pop_value()?;
         interpreter_state.push_value((value1 > value2) as VariableValue);
@@ -144,7 +433,7 @@ impl Interpreter {
         Ok(())
     }
 
-    fn interpret_less_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), String> {
+    fn interpret_less_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
         let value1 = interpreter_state.pop_value()?;
         let value2= interpreter_state.pop_value()?;
         interpreter_state.push_value((value1 < value2) as VariableValue);
@@ -152,7 +441,7 @@ impl Interpreter {
         Ok(())
     }
 
-    fn interpret_equal_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), String> {
+    fn interpret_equal_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
         let value1 = interpreter_state.pop_value()?;
         let value2= interpreter_state.pop_value()?;
         interpreter_state.push_value((value1 == value2) as VariableValue);
@@ -160,7 +449,7 @@ impl Interpreter {
         Ok(())
     }
 
-    fn interpret_dup_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), String> {
+    fn interpret_dup_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
         let value = interpreter_state.pop_value()?;
         interpreter_state.push_value(value);
         interpreter_state.push_value(value);
@@ -168,46 +457,91 @@ impl Interpreter {
         Ok(())
     }
 
-    fn interpret_pop_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), String> {
+    fn interpret_pop_instruction(&mut self, interpreter_state: &mut InterpreterState) -> Result<(), RuntimeError> {
         interpreter_state.pop_value()?;
         interpreter_state.next();
         Ok(())
     }
 
-    fn interpret_goto_instruction(&mut self, interpreter_state: &mut InterpreterState, instruction: Instruction) -> Result<(), String> {
-        let label = instruction.variable.unwrap();
-        let label_instruction_number = match self.bytecode.get_label(&label) {
-            Ok(number) => number.clone(),
-            Err(e) => return Err(e)
-        };
+    fn interpret_jump_instruction(&mut self, interpreter_state: &mut InterpreterState, target: usize) -> Result<(), RuntimeError> {
+        interpreter_state.set_instruction_pointer(target);
+        Ok(())
+    }
+
+    /// Pops a value and jumps if its truthiness (any non-zero value is true) equals `jump_if`.
+    fn interpret_conditional_jump_instruction(
+        &mut self,
+        interpreter_state: &mut InterpreterState,
+        target: usize,
+        jump_if: bool,
+    ) -> Result<(), RuntimeError> {
         let value = interpreter_state.pop_value()?;
-        if value == 0 {
-            interpreter_state.next();
+        if (value != 0) == jump_if {
+            interpreter_state.set_instruction_pointer(target)
         } else {
-            interpreter_state.set_instruction_pointer(label_instruction_number as usize)
+            interpreter_state.next();
+        }
+        Ok(())
+    }
+
+    fn interpret_call_instruction(&mut self, interpreter_state: &mut InterpreterState, target: usize) -> Result<(), RuntimeError> {
+        interpreter_state.call(target, self.config.max_call_depth)
+    }
+
+    fn interpret_spawn_instruction(&mut self, interpreter_state: &mut InterpreterState, target: usize) -> Result<(), RuntimeError> {
+        if self.live_tasks >= self.config.max_tasks {
+            return Err(RuntimeError::new(
+                interpreter_state.get_instruction_pointer(),
+                RuntimeErrorKind::TaskLimitExceeded(self.config.max_tasks),
+            ));
+        }
+        self.live_tasks += 1;
+        let mut task = InterpreterState::new();
+        task.set_instruction_pointer(target);
+        self.spawned_tasks.push(task);
+        interpreter_state.next();
+        Ok(())
+    }
+
+    fn interpret_send_instruction(&mut self, interpreter_state: &mut InterpreterState, channel: usize) -> Result<(), RuntimeError> {
+        if self.queued_values >= self.config.max_queued_values {
+            return Err(RuntimeError::new(
+                interpreter_state.get_instruction_pointer(),
+                RuntimeErrorKind::ChannelLimitExceeded(self.config.max_queued_values),
+            ));
         }
+        let value = interpreter_state.pop_value()?;
+        self.queued_values += 1;
+        self.channels[channel].push_back(value);
+        interpreter_state.next();
         Ok(())
     }
+
+    fn interpret_recv_instruction(&mut self, interpreter_state: &mut InterpreterState, channel: usize) -> Result<TaskStatus, RuntimeError> {
+        match self.channels[channel].pop_front() {
+            None => Ok(TaskStatus::Blocked),
+            Some(value) => {
+                self.queued_values -= 1;
+                interpreter_state.push_value(value);
+                interpreter_state.next();
+                Ok(TaskStatus::Running)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::bytecode::instruction::Instruction;
+    use crate::config::{GasCosts, OverflowMode};
 
     #[test]
     fn test_interpret_load() {
         let load_value: VariableValue = 10;
         let mut interpreter_state = InterpreterState::new();
-        let load_instruction = Instruction {
-            name: InstructionName::LOAD,
-            arg: Some(load_value),
-            variable: None,
-        };
-
         let mut interpreter = Interpreter::new(ByteCode::new());
 
-        let result = Interpreter::interpret_load_instruction(&mut interpreter, &mut interpreter_state, load_instruction);
+        let result = Interpreter::interpret_load_instruction(&mut interpreter, &mut interpreter_state, load_value);
 
         assert!(result.is_ok());
         let on_stack = interpreter_state.pop_value();
@@ -219,41 +553,31 @@ mod tests {
     #[test]
     fn test_interpret_write() {
         let load_value: VariableValue = 10;
-        let var_name = "x";
+        let var_slot = 0;
         let mut interpreter_state = InterpreterState::new();
         interpreter_state.push_value(load_value);
         interpreter_state.next();
-        let load_instruction = Instruction {
-            name: InstructionName::WRITE,
-            arg: None,
-            variable: Some(var_name.to_string()),
-        };
 
         let mut interpreter = Interpreter::new(ByteCode::new());
 
-        let result = Interpreter::interpret_write_instruction(&mut interpreter, &mut interpreter_state, load_instruction);
+        let result = Interpreter::interpret_write_instruction(&mut interpreter, &mut interpreter_state, var_slot);
 
         assert!(result.is_ok());
-        assert_eq!(*interpreter_state.vars.get(var_name).unwrap(), load_value);
+        assert_eq!(interpreter_state.read_var(var_slot), Some(load_value));
         assert_eq!(interpreter_state.instruction_pointer, 0x2);
     }
 
     #[test]
     fn test_interpret_write_with_empty_stack() {
-        let var_name = "x";
+        let var_slot = 0;
         let mut interpreter_state = InterpreterState::new();
-        let load_instruction = Instruction {
-            name: InstructionName::WRITE,
-            arg: None,
-            variable: Some(var_name.to_string()),
-        };
 
         let mut interpreter = Interpreter::new(ByteCode::new());
 
-        let result = Interpreter::interpret_write_instruction(&mut interpreter, &mut interpreter_state, load_instruction);
+        let result = Interpreter::interpret_write_instruction(&mut interpreter, &mut interpreter_state, var_slot);
 
         assert_eq!(
-            result,
+            result.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
         )
     }
@@ -261,18 +585,13 @@ mod tests {
     #[test]
     fn test_interpret_read() {
         let load_value: VariableValue = 10;
-        let var_name = "x";
+        let var_slot = 0;
         let mut interpreter_state = InterpreterState::new();
-        interpreter_state.vars.insert(var_name.to_string(), load_value);
-        let load_instruction = Instruction {
-            name: InstructionName::READ,
-            arg: None,
-            variable: Some(var_name.to_string()),
-        };
+        interpreter_state.add_var(var_slot, load_value, 1).unwrap();
 
         let mut interpreter = Interpreter::new(ByteCode::new());
 
-        let result = Interpreter::interpret_read_instruction(&mut interpreter, &mut interpreter_state, load_instruction);
+        let result = Interpreter::interpret_read_instruction(&mut interpreter, &mut interpreter_state, var_slot);
 
         assert!(result.is_ok());
         let on_stack = interpreter_state.pop_value();
@@ -283,21 +602,18 @@ mod tests {
 
     #[test]
     fn test_interpret_read_with_not_existing_variable() {
-        let var_name = "x";
+        let var_slot = 0;
         let mut interpreter_state = InterpreterState::new();
-        let load_instruction = Instruction {
-            name: InstructionName::READ,
-            arg: None,
-            variable: Some(var_name.to_string()),
-        };
+        let code = "READ_VAR x";
+        let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
 
-        let mut interpreter = Interpreter::new(ByteCode::new());
+        let mut interpreter = Interpreter::new(bytecode);
 
-        let result = Interpreter::interpret_read_instruction(&mut interpreter, &mut interpreter_state, load_instruction);
+        let result = Interpreter::interpret_read_instruction(&mut interpreter, &mut interpreter_state, var_slot);
 
         assert_eq!(
-            result,
-            Err("Runtime error: unable to get variable: doesn't exist, instruction#1".to_string())
+            result.map_err(|e| e.to_string()),
+            Err("Runtime error: unable to get variable x: doesn't exist, instruction#1".to_string())
         )
     }
 
@@ -331,7 +647,7 @@ mod tests {
         let result = Interpreter::interpret_add_instruction(&mut interpreter, &mut interpreter_state);
 
         assert_eq!(
-            result,
+            result.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
         )
     }
@@ -366,7 +682,7 @@ mod tests {
         let result = Interpreter::interpret_multiply_instruction(&mut interpreter, &mut interpreter_state);
 
         assert_eq!(
-            result,
+            result.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
         )
     }
@@ -401,7 +717,7 @@ mod tests {
         let result = Interpreter::interpret_greater_instruction(&mut interpreter, &mut interpreter_state);
 
         assert_eq!(
-            result,
+            result.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
         )
     }
@@ -436,7 +752,7 @@ mod tests {
         let result = Interpreter::interpret_less_instruction(&mut interpreter, &mut interpreter_state);
 
         assert_eq!(
-            result,
+            result.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
         )
     }
@@ -471,7 +787,7 @@ mod tests {
         let result = Interpreter::interpret_equal_instruction(&mut interpreter, &mut interpreter_state);
 
         assert_eq!(
-            result,
+            result.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
         )
     }
@@ -505,7 +821,7 @@ mod tests {
         let result = Interpreter::interpret_dup_instruction(&mut interpreter, &mut interpreter_state);
 
         assert_eq!(
-            result,
+            result.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
         )
     }
@@ -523,7 +839,7 @@ mod tests {
         assert!(result.is_ok());
         let on_stack = interpreter_state.pop_value();
         assert_eq!(
-            on_stack,
+            on_stack.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #2: no value on stack".to_string())
         )
     }
@@ -537,8 +853,499 @@ mod tests {
         let result = Interpreter::interpret_pop_instruction(&mut interpreter, &mut interpreter_state);
 
         assert_eq!(
-            result,
+            result.map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
+        )
+    }
+
+    #[test]
+    fn test_interpret_send_and_recv() {
+        let load_value: VariableValue = 10;
+        let mut sender_state = InterpreterState::new();
+        let mut receiver_state = InterpreterState::new();
+        sender_state.push_value(load_value);
+
+        let mut interpreter = Interpreter::new(ByteCode::parse_instructions(&mut "SEND_CHANNEL ch".as_bytes()).unwrap());
+
+        let result = Interpreter::interpret_send_instruction(&mut interpreter, &mut sender_state, 0);
+        assert!(result.is_ok());
+        assert_eq!(sender_state.instruction_pointer, 0x1);
+
+        let result = Interpreter::interpret_recv_instruction(&mut interpreter, &mut receiver_state, 0);
+        assert!(matches!(result, Ok(TaskStatus::Running)));
+        let on_stack = receiver_state.pop_value();
+        assert_eq!(on_stack, Ok(load_value));
+        assert_eq!(receiver_state.instruction_pointer, 0x1);
+    }
+
+    #[test]
+    fn test_interpret_recv_with_empty_channel() {
+        let mut interpreter_state = InterpreterState::new();
+
+        let mut interpreter = Interpreter::new(ByteCode::parse_instructions(&mut "SEND_CHANNEL ch".as_bytes()).unwrap());
+
+        let result = Interpreter::interpret_recv_instruction(&mut interpreter, &mut interpreter_state, 0);
+
+        assert!(matches!(result, Ok(TaskStatus::Blocked)));
+        assert_eq!(interpreter_state.instruction_pointer, 0x0);
+    }
+
+    #[test]
+    fn test_interpret_send_with_bad_stack_state() {
+        let mut interpreter_state = InterpreterState::new();
+
+        let mut interpreter = Interpreter::new(ByteCode::parse_instructions(&mut "SEND_CHANNEL ch".as_bytes()).unwrap());
+
+        let result = Interpreter::interpret_send_instruction(&mut interpreter, &mut interpreter_state, 0);
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
+        )
+    }
+
+    #[test]
+    fn test_interpret_call_and_ret() {
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.add_var(0, 10, 1).unwrap();
+        interpreter_state.next();
+
+        interpreter_state.call(5, 1).unwrap();
+        assert_eq!(interpreter_state.instruction_pointer, 0x5);
+        assert_eq!(interpreter_state.read_var(0), None);
+
+        interpreter_state.add_var(0, 20, 1).unwrap();
+        let result = interpreter_state.ret();
+
+        assert!(result.is_ok());
+        assert_eq!(interpreter_state.read_var(0), Some(10));
+        assert_eq!(interpreter_state.instruction_pointer, 0x2);
+    }
+
+    #[test]
+    fn test_interpret_call_with_exceeded_depth() {
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.call(0, 1).unwrap();
+
+        let result = interpreter_state.call(0, 1);
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #1: maximum call depth of 1 exceeded".to_string())
+        )
+    }
+
+    #[test]
+    fn test_interpret_ret_outside_of_function() {
+        let mut interpreter_state = InterpreterState::new();
+
+        let result = interpreter_state.ret();
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #1: RET outside of a function call".to_string())
+        )
+    }
+
+    #[test]
+    fn test_interpret_add_with_overflow() {
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(VariableValue::MAX);
+        interpreter_state.push_value(1);
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_add_instruction(&mut interpreter, &mut interpreter_state);
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #1: ADD overflow".to_string())
+        )
+    }
+
+    #[test]
+    fn test_interpret_multiply_with_saturating_overflow() {
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(VariableValue::MAX);
+        interpreter_state.push_value(2);
+
+        let config = InterpreterConfig {
+            overflow_mode: OverflowMode::Saturating,
+            ..InterpreterConfig::default()
+        };
+        let mut interpreter = Interpreter::with_config(ByteCode::new(), config);
+
+        let result = Interpreter::interpret_multiply_instruction(&mut interpreter, &mut interpreter_state);
+
+        assert!(result.is_ok());
+        assert_eq!(interpreter_state.pop_value(), Ok(VariableValue::MAX));
+        assert_eq!(interpreter_state.instruction_pointer, 0x1);
+    }
+
+    #[test]
+    fn test_interpret_sub() {
+        let load_value1: VariableValue = 3;
+        let load_value2: VariableValue = 10;
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(load_value1);
+        interpreter_state.push_value(load_value2);
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_sub_instruction(&mut interpreter, &mut interpreter_state);
+
+        assert!(result.is_ok());
+        let on_stack = interpreter_state.pop_value();
+        assert_eq!(on_stack, Ok(load_value2 - load_value1));
+        assert_eq!(interpreter_state.instruction_pointer, 0x1);
+    }
+
+    #[test]
+    fn test_interpret_div() {
+        let load_value1: VariableValue = 3;
+        let load_value2: VariableValue = 10;
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(load_value1);
+        interpreter_state.push_value(load_value2);
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_div_instruction(&mut interpreter, &mut interpreter_state);
+
+        assert!(result.is_ok());
+        let on_stack = interpreter_state.pop_value();
+        assert_eq!(on_stack, Ok(3));
+        assert_eq!(interpreter_state.instruction_pointer, 0x1);
+    }
+
+    #[test]
+    fn test_interpret_div_by_zero() {
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(0);
+        interpreter_state.push_value(10);
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_div_instruction(&mut interpreter, &mut interpreter_state);
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #1: DIV division by zero".to_string())
+        )
+    }
+
+    #[test]
+    fn test_interpret_mod() {
+        let load_value1: VariableValue = 3;
+        let load_value2: VariableValue = 10;
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(load_value1);
+        interpreter_state.push_value(load_value2);
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_mod_instruction(&mut interpreter, &mut interpreter_state);
+
+        assert!(result.is_ok());
+        let on_stack = interpreter_state.pop_value();
+        assert_eq!(on_stack, Ok(1));
+        assert_eq!(interpreter_state.instruction_pointer, 0x1);
+    }
+
+    #[test]
+    fn test_interpret_neg_with_bad_stack_state() {
+        let mut interpreter_state = InterpreterState::new();
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_neg_instruction(&mut interpreter, &mut interpreter_state);
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
+        )
+    }
+
+    #[test]
+    fn test_interpret_xor() {
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(0b1010);
+        interpreter_state.push_value(0b1100);
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_bitwise_instruction(&mut interpreter, &mut interpreter_state, bitwise::xor);
+
+        assert!(result.is_ok());
+        assert_eq!(interpreter_state.pop_value(), Ok(0b0110));
+        assert_eq!(interpreter_state.instruction_pointer, 0x1);
+    }
+
+    #[test]
+    fn test_interpret_not_with_bad_stack_state() {
+        let mut interpreter_state = InterpreterState::new();
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_not_instruction(&mut interpreter, &mut interpreter_state);
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
             Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
         )
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_interpret_shl() {
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(3);
+        interpreter_state.push_value(1);
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_shift_instruction(&mut interpreter, &mut interpreter_state, InstructionName::SHL, bitwise::shl);
+
+        assert!(result.is_ok());
+        assert_eq!(interpreter_state.pop_value(), Ok(0b1000));
+        assert_eq!(interpreter_state.instruction_pointer, 0x1);
+    }
+
+    #[test]
+    fn test_interpret_jump() {
+        let mut interpreter_state = InterpreterState::new();
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        let result = Interpreter::interpret_jump_instruction(&mut interpreter, &mut interpreter_state, 5);
+
+        assert!(result.is_ok());
+        assert_eq!(interpreter_state.stack.len(), 0);
+        assert_eq!(interpreter_state.instruction_pointer, 0x5);
+    }
+
+    #[test]
+    fn test_interpret_conditional_jump() {
+
+        let mut interpreter = Interpreter::new(ByteCode::new());
+
+        for (value, jump_if, instruction_pointer) in [(0, false, 0x5), (2, false, 0x1), (2, true, 0x5), (0, true, 0x1)] {
+            let mut interpreter_state = InterpreterState::new();
+            interpreter_state.push_value(value);
+
+            let result = Interpreter::interpret_conditional_jump_instruction(
+                &mut interpreter, &mut interpreter_state, 5, jump_if
+            );
+
+            assert!(result.is_ok());
+            assert_eq!(interpreter_state.instruction_pointer, instruction_pointer);
+        }
+    }
+
+    #[test]
+    fn test_interpret_with_exhausted_instruction_budget() {
+        let code = ".loop\nLOAD_VAL 1\nGOTO .loop";
+        let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+        let config = InterpreterConfig {
+            max_instructions: Some(11),
+            ..InterpreterConfig::default()
+        };
+
+        let mut interpreter = Interpreter::with_config(bytecode, config);
+
+        assert_eq!(
+            interpreter.interpret().map_err(|e| e.to_string()),
+            Err("Runtime error: instruction budget exhausted after 11 steps, next instruction #2 (line 3, column 1)".to_string())
+        );
+        assert_eq!(
+            interpreter.interpret().map_err(|e| e.to_string()),
+            Err("Runtime error: instruction budget exhausted after 11 steps, next instruction #2 (line 3, column 1)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_interpret_within_instruction_budget() {
+        let code = "LOAD_VAL 1\nRETURN_VALUE";
+        let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+        let config = InterpreterConfig {
+            max_instructions: Some(2),
+            ..InterpreterConfig::default()
+        };
+
+        let mut interpreter = Interpreter::with_config(bytecode, config);
+
+        assert_eq!(interpreter.interpret(), Ok(Some(1)));
+    }
+
+    #[test]
+    fn test_run_reports_gas_used() {
+        let code = "LOAD_VAL 2\nLOAD_VAL 3\nMULTIPLY\nRETURN_VALUE";
+        let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+
+        let mut interpreter = Interpreter::new(bytecode);
+
+        assert_eq!(
+            interpreter.run(),
+            Ok(ExecutionReport {
+                value: Some(6),
+                executed_instructions: 4,
+                gas_used: 6,
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_out_of_gas() {
+        let code = "LOAD_VAL 2\nLOAD_VAL 3\nMULTIPLY\nRETURN_VALUE";
+        let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+        let mut gas_costs = GasCosts::new(1);
+        gas_costs.set_cost(InstructionName::MULTIPLY, 10);
+        let config = InterpreterConfig {
+            gas_limit: Some(11),
+            gas_costs,
+            ..InterpreterConfig::default()
+        };
+
+        let mut interpreter = Interpreter::with_config(bytecode, config);
+
+        assert_eq!(
+            interpreter.run().map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #3 (line 3, column 1): out of gas, 2 of 11 used, MULTIPLY costs 10".to_string())
+        );
+        assert_eq!(interpreter.gas_used(), 2);
+    }
+
+    #[test]
+    fn test_run_with_gas_costs_near_the_maximum() {
+        let code = "LOAD_VAL 2\nLOAD_VAL 3\nMULTIPLY\nRETURN_VALUE";
+        let mut gas_costs = GasCosts::new(1);
+        gas_costs.set_cost(InstructionName::MULTIPLY, u64::MAX - 1);
+        let config = InterpreterConfig {
+            gas_limit: Some(u64::MAX),
+            gas_costs: gas_costs.clone(),
+            ..InterpreterConfig::default()
+        };
+        let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+        let mut interpreter = Interpreter::with_config(bytecode, config);
+
+        assert_eq!(
+            interpreter.run().map_err(|e| e.kind),
+            Err(RuntimeErrorKind::OutOfGas {
+                used: 2,
+                limit: u64::MAX,
+                cost: u64::MAX - 1,
+            })
+        );
+        assert_eq!(interpreter.gas_used(), 2);
+
+        let config = InterpreterConfig {
+            gas_costs,
+            ..InterpreterConfig::default()
+        };
+        let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+        let mut interpreter = Interpreter::with_config(bytecode, config);
+
+        assert_eq!(interpreter.run().map(|report| report.gas_used), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn test_interpret_write_with_exceeded_variables() {
+        let mut interpreter_state = InterpreterState::new();
+        interpreter_state.push_value(1);
+        interpreter_state.push_value(2);
+        interpreter_state.push_value(3);
+        let config = InterpreterConfig {
+            max_variables: 2,
+            ..InterpreterConfig::default()
+        };
+
+        let mut interpreter = Interpreter::with_config(ByteCode::new(), config);
+
+        assert!(Interpreter::interpret_write_instruction(&mut interpreter, &mut interpreter_state, 0).is_ok());
+        assert!(Interpreter::interpret_write_instruction(&mut interpreter, &mut interpreter_state, 4).is_ok());
+        assert_eq!(
+            Interpreter::interpret_write_instruction(&mut interpreter, &mut interpreter_state, 3).map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #3: maximum number of 2 variables exceeded".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_with_exceeded_stack_depth() {
+        let code = ".loop\nLOAD_VAL 1\nJUMP .loop";
+        let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+        let config = InterpreterConfig {
+            max_stack_depth: 8,
+            ..InterpreterConfig::default()
+        };
+
+        let mut interpreter = Interpreter::with_config(bytecode, config);
+
+        assert_eq!(
+            interpreter.run().map_err(|e| e.to_string()),
+            Err("Runtime error: unable to process instruction #1 (line 2, column 1): maximum stack depth of 8 exceeded".to_string())
+        );
+    }
+
+    #[test]
+    fn test_variable_limit_per_frame() {
+        let mut interpreter_state = InterpreterState::new();
+        assert!(interpreter_state.add_var(0, 1, 1).is_ok());
+        assert!(interpreter_state.call(5, 8).is_ok());
+        assert!(interpreter_state.add_var(0, 2, 1).is_ok());
+        assert!(interpreter_state.add_var(0, 3, 1).is_ok());
+        assert_eq!(
+            interpreter_state.add_var(1, 4, 1).map_err(|e| e.kind),
+            Err(RuntimeErrorKind::VariableLimitExceeded(1))
+        );
+        assert!(interpreter_state.ret().is_ok());
+        assert!(interpreter_state.add_var(0, 5, 1).is_ok());
+        assert_eq!(
+            interpreter_state.add_var(2, 6, 1).map_err(|e| e.kind),
+            Err(RuntimeErrorKind::VariableLimitExceeded(1))
+        );
+        assert_eq!(interpreter_state.read_var(0), Some(5));
+    }
+
+    #[test]
+    fn test_run_with_exceeded_tasks() {
+        let code = "LOAD_VAL 3\nWRITE_VAR n\n.loop\nSPAWN .worker\nLOAD_VAL 1\nREAD_VAR n\nSUB\nDUP\nWRITE_VAR n\n\
+            JUMP_IF_TRUE .loop\nLOAD_VAL 7\nRETURN_VALUE\n.worker\nLOAD_VAL 0\nRETURN_VALUE";
+        let run = |max_tasks| {
+            let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+            let config = InterpreterConfig {
+                max_tasks,
+                ..InterpreterConfig::default()
+            };
+            Interpreter::with_config(bytecode, config).interpret().map_err(|e| e.to_string())
+        };
+
+        assert_eq!(run(2), Ok(Some(7)));
+        assert_eq!(
+            run(1),
+            Err("Runtime error: unable to process instruction #3 (line 4, column 1): maximum number of 1 tasks exceeded".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_with_exceeded_queued_values() {
+        let run = |code: &str| {
+            let bytecode = ByteCode::parse_instructions(&mut code.as_bytes()).unwrap();
+            let config = InterpreterConfig {
+                max_queued_values: 2,
+                ..InterpreterConfig::default()
+            };
+            Interpreter::with_config(bytecode, config).interpret().map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            run("LOAD_VAL 1\nSEND_CHANNEL a\nLOAD_VAL 2\nSEND_CHANNEL a\nRECV_CHANNEL a\nLOAD_VAL 3\nSEND_CHANNEL b\n\
+                RECV_CHANNEL a\nRETURN_VALUE"),
+            Ok(Some(2))
+        );
+        assert_eq!(
+            run(".loop\nLOAD_VAL 1\nSEND_CHANNEL a\nJUMP .loop"),
+            Err("Runtime error: unable to process instruction #2 (line 3, column 1): \
+                maximum number of 2 queued channel values exceeded"
+                .to_string())
+        );
+    }
+}
@@ -1,11 +1,26 @@
+pub(crate) mod arithmetic;
+pub(crate) mod bitwise;
+#[allow(clippy::module_inception)]
 mod interpreter;
 mod stack;
 
 use crate::bytecode::ByteCode;
-use interpreter::Interpreter;
+pub use interpreter::{ExecutionReport, Interpreter};
+use crate::config::InterpreterConfig;
+use crate::error::RuntimeError;
 use crate::VariableValue;
 
-pub fn interpret(program: ByteCode) -> Result<Option<VariableValue>, String>  {
+pub fn interpret(program: ByteCode) -> Result<Option<VariableValue>, RuntimeError>  {
     let mut interpreter = Interpreter::new(program);
     interpreter.interpret()
-}
\ No newline at end of file
+}
+
+pub fn interpret_with_config(program: ByteCode, config: InterpreterConfig) -> Result<Option<VariableValue>, RuntimeError> {
+    let mut interpreter = Interpreter::with_config(program, config);
+    interpreter.interpret()
+}
+
+pub fn run_with_config(program: ByteCode, config: InterpreterConfig) -> Result<ExecutionReport, RuntimeError> {
+    let mut interpreter = Interpreter::with_config(program, config);
+    interpreter.run()
+}
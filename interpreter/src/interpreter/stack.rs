@@ -15,4 +15,8 @@ impl<T> Stack<T> {
     pub fn pop(&mut self) -> Option<T> {
         self.stack.pop()
     }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
 }
\ No newline at end of file
@@ -1,12 +1,36 @@
+mod analysis;
 mod bytecode;
 mod interpreter;
 mod config;
+mod error;
 
-use bytecode::ByteCode;
-use interpreter::interpret as int;
-use crate::config::VariableValue;
+pub use analysis::cfg::{BasicBlock, ControlFlowGraph, Edge, EdgeKind};
+pub use analysis::stack::{stack_effect, verify_stack};
+pub use analysis::variables::{check_variables, Severity, VariableDiagnostic, VariableDiagnosticKind};
+pub use bytecode::disassembler::{disassemble, DisassemblyOptions};
+pub use bytecode::formatter::{format_file, format_source, is_formatted};
+pub use bytecode::lexer::{tokenize, Span, Token, TokenKind};
+pub use bytecode::optimizer::optimize;
+pub use bytecode::{ByteCode, Comment, CommentPlacement, SourcePosition};
+pub use interpreter::{ExecutionReport, Interpreter};
+use interpreter::{interpret as int, interpret_with_config as int_with_config, run_with_config as run_int};
+pub use crate::config::{GasCosts, InstructionName, InterpreterConfig, OverflowMode, VariableValue};
+pub use crate::error::{
+    Error, LoadError, LoadErrorKind, ParseError, ParseErrorKind, ParseErrors, RuntimeError, RuntimeErrorKind,
+    VerifyError, VerifyErrorKind,
+};
 
-pub fn interpret(source_file: &str) -> Result<Option<VariableValue>, String> {
+pub fn interpret(source_file: &str) -> Result<Option<VariableValue>, Error> {
     let byte_code = ByteCode::parse_file(source_file)?;
-    int(byte_code)
-}
\ No newline at end of file
+    Ok(int(byte_code)?)
+}
+
+pub fn interpret_with_config(source_file: &str, config: InterpreterConfig) -> Result<Option<VariableValue>, Error> {
+    let byte_code = ByteCode::parse_file(source_file)?;
+    Ok(int_with_config(byte_code, config)?)
+}
+
+pub fn run_with_config(source_file: &str, config: InterpreterConfig) -> Result<ExecutionReport, Error> {
+    let byte_code = ByteCode::parse_file(source_file)?;
+    Ok(run_int(byte_code, config)?)
+}
@@ -1,5 +1,11 @@
+use std::fmt::Display;
+use std::fs;
+use std::process;
 use clap::{App, Arg};
-use interpreter_app::interpret;
+use interpreter_app::{
+    check_variables, disassemble, format_file, optimize, verify_stack, ByteCode, ControlFlowGraph, DisassemblyOptions, Interpreter,
+    InterpreterConfig, Severity,
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let matches = App::new("interpreter")
@@ -15,12 +21,218 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .help("Sets file with ByteCode instructions")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("CHECK")
+                .long("check")
+                .help("Reports every parse error of the file without running it"),
+        )
+        .arg(
+            Arg::with_name("FORMAT")
+                .long("format")
+                .help("Rewrites the file in the canonical form without running it"),
+        )
+        .arg(
+            Arg::with_name("FORMAT_CHECK")
+                .long("format-check")
+                .help("Fails if the file isn't in the canonical form, without changing or running it"),
+        )
+        .arg(
+            Arg::with_name("VERIFY")
+                .long("verify")
+                .help("Checks the stack and the variables of the program before running it"),
+        )
+        .arg(
+            Arg::with_name("OPTIMIZE")
+                .long("optimize")
+                .help("Folds constants, removes unreachable code and applies peephole rewrites before running"),
+        )
+        .arg(
+            Arg::with_name("COMPILE")
+                .long("compile")
+                .help("Writes the program to this file in the binary format without running it")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("DISASSEMBLE")
+                .long("disassemble")
+                .help("Prints the program in the text syntax without running it"),
+        )
+        .arg(
+            Arg::with_name("INDICES")
+                .long("indices")
+                .requires("DISASSEMBLE")
+                .help("Adds the number of every instruction to the disassembly"),
+        )
+        .arg(
+            Arg::with_name("LINES")
+                .long("lines")
+                .requires("DISASSEMBLE")
+                .help("Adds the source line of every instruction to the disassembly"),
+        )
+        .arg(
+            Arg::with_name("DOT")
+                .long("dot")
+                .help("Writes the control-flow graph of the program to this Graphviz DOT file without running it")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MAX_CALL_DEPTH")
+                .long("max-call-depth")
+                .help("Sets maximum number of nested CALL instructions")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MAX_STACK_DEPTH")
+                .long("max-stack-depth")
+                .help("Sets maximum number of values on the stack of a task")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MAX_VARIABLES")
+                .long("max-variables")
+                .help("Sets maximum number of distinct variables in a function call")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MAX_TASKS")
+                .long("max-tasks")
+                .help("Sets maximum number of unfinished tasks, the main task included")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MAX_QUEUED_VALUES")
+                .long("max-queued-values")
+                .help("Sets maximum number of values sent to channels and not received yet")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("OVERFLOW")
+                .long("overflow")
+                .help("Sets arithmetic overflow mode")
+                .possible_values(["checked", "wrapping", "saturating"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("MAX_INSTRUCTIONS")
+                .long("max-instructions")
+                .help("Stops the program after executing this number of instructions")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("GAS_LIMIT")
+                .long("gas-limit")
+                .help("Stops the program when executing the next instruction would exceed this amount of gas")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("REPORT_GAS")
+                .long("report-gas")
+                .help("Prints the gas used by the program after the result"),
+        )
         .get_matches();
 
     let file = matches
         .value_of("FILE")
         .expect("File must be specified");
-    let result = interpret(file)?;
-    println!("{}", result.unwrap());
+    if matches.is_present("CHECK") {
+        let (_, errors) = ByteCode::parse_file_recovering(file);
+        if !errors.is_empty() {
+            exit_with_errors(&errors);
+        }
+        return Ok(());
+    }
+    if matches.is_present("FORMAT") || matches.is_present("FORMAT_CHECK") {
+        let formatted = match format_file(file) {
+            Ok(formatted) => formatted,
+            Err(e) => exit_with_errors(e.errors()),
+        };
+        if matches.is_present("FORMAT") {
+            fs::write(file, formatted)?;
+        } else if formatted != fs::read_to_string(file)? {
+            exit_with_errors(&[format!("{} is not formatted", file)]);
+        }
+        return Ok(());
+    }
+    let mut config = InterpreterConfig::default();
+    if let Some(max_call_depth) = matches.value_of("MAX_CALL_DEPTH") {
+        config.max_call_depth = max_call_depth.parse()?;
+    }
+    if let Some(max_stack_depth) = matches.value_of("MAX_STACK_DEPTH") {
+        config.max_stack_depth = max_stack_depth.parse()?;
+    }
+    if let Some(max_variables) = matches.value_of("MAX_VARIABLES") {
+        config.max_variables = max_variables.parse()?;
+    }
+    if let Some(max_tasks) = matches.value_of("MAX_TASKS") {
+        config.max_tasks = max_tasks.parse()?;
+    }
+    if let Some(max_queued_values) = matches.value_of("MAX_QUEUED_VALUES") {
+        config.max_queued_values = max_queued_values.parse()?;
+    }
+    if let Some(overflow_mode) = matches.value_of("OVERFLOW") {
+        config.overflow_mode = overflow_mode.parse()?;
+    }
+    if let Some(max_instructions) = matches.value_of("MAX_INSTRUCTIONS") {
+        config.max_instructions = Some(max_instructions.parse()?);
+    }
+    if let Some(gas_limit) = matches.value_of("GAS_LIMIT") {
+        config.gas_limit = Some(gas_limit.parse()?);
+    }
+    let mut bytecode = match fs::read(file) {
+        Ok(bytes) if ByteCode::is_binary(&bytes) => match ByteCode::from_binary(&bytes) {
+            Ok(bytecode) => bytecode,
+            Err(e) => exit_with_errors(&[e]),
+        },
+        _ => match ByteCode::parse_file(file) {
+            Ok(bytecode) => bytecode,
+            Err(e) => exit_with_errors(&[e]),
+        },
+    };
+    if matches.is_present("OPTIMIZE") {
+        bytecode = optimize(&bytecode);
+    }
+    if let Some(binary_file) = matches.value_of("COMPILE") {
+        bytecode.write_binary_file(binary_file)?;
+        return Ok(());
+    }
+    if matches.is_present("DISASSEMBLE") {
+        let options = DisassemblyOptions {
+            instruction_indices: matches.is_present("INDICES"),
+            line_numbers: matches.is_present("LINES"),
+        };
+        print!("{}", disassemble(&bytecode, options));
+        return Ok(());
+    }
+    if let Some(dot_file) = matches.value_of("DOT") {
+        fs::write(dot_file, ControlFlowGraph::new(&bytecode).to_dot(&bytecode))?;
+        return Ok(());
+    }
+    if matches.is_present("VERIFY") {
+        if let Err(errors) = verify_stack(&bytecode) {
+            exit_with_errors(&errors);
+        }
+        let diagnostics = check_variables(&bytecode);
+        if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+            exit_with_errors(&diagnostics);
+        }
+        for diagnostic in &diagnostics {
+            eprintln!("{}", diagnostic);
+        }
+    }
+    let report = match Interpreter::with_config(bytecode, config).run() {
+        Ok(report) => report,
+        Err(e) => exit_with_errors(&[e]),
+    };
+    println!("{}", report.value.unwrap());
+    if matches.is_present("REPORT_GAS") {
+        println!("gas used: {}", report.gas_used);
+    }
     Ok(())
 }
+
+fn exit_with_errors<E: Display>(errors: &[E]) -> ! {
+    for error in errors {
+        eprintln!("{}", error);
+    }
+    process::exit(1);
+}
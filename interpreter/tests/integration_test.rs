@@ -1,4 +1,7 @@
-use interpreter_app::interpret;
+use interpreter_app::{
+    interpret, interpret_with_config, run_with_config, check_variables, disassemble, format_file, is_formatted, optimize, verify_stack, ByteCode, ControlFlowGraph, DisassemblyOptions, Error, GasCosts, InstructionName, Interpreter, InterpreterConfig, LoadErrorKind,
+    OverflowMode, ParseErrorKind, RuntimeErrorKind, VariableValue,
+};
 use std::io::Write;
 use tempfile::NamedTempFile;
 
@@ -13,7 +16,7 @@ pub fn test_empty_program() {
             .to_str()
             .expect("Unable to convert temp file path to string"),
     );
-    assert_eq!(result, Err("Empty program".to_string()));
+    assert_eq!(result.map_err(|e| e.to_string()), Err("Empty program".to_string()));
 }
 
 #[test]
@@ -28,8 +31,8 @@ pub fn test_empty_stack() {
             .expect("Unable to convert temp file path to string"),
     );
     assert_eq!(
-        result,
-        Err("Runtime error: unable to process instruction #1: no value on stack".to_string())
+        result.map_err(|e| e.to_string()),
+        Err("Runtime error: unable to process instruction #1 (line 1, column 1): no value on stack".to_string())
     );
 }
 
@@ -49,8 +52,8 @@ pub fn test_bad_instruction() {
             .expect("Unable to convert temp file path to string"),
     );
     assert_eq!(
-        result,
-        Err("Unable to parse line #3: Error creating load instruction: expected 1 argument, got 0".to_string())
+        result.map_err(|e| e.to_string()),
+        Err("Unable to parse line #3, column 17: Error creating load instruction: expected 1 argument, got 0".to_string())
     );
 }
 
@@ -69,8 +72,8 @@ pub fn test_duplicated_labels() {
             .expect("Unable to convert temp file path to string"),
     );
     assert_eq!(
-        result,
-        Err("Unable to parse line #2: duplicated label: .label".to_string())
+        result.map_err(|e| e.to_string()),
+        Err("Unable to parse line #2, column 9: duplicated label: .label".to_string())
     );
 }
 
@@ -86,8 +89,8 @@ pub fn test_non_existent_label() {
             .expect("Unable to convert temp file path to string"),
     );
     assert_eq!(
-        result,
-        Err("Label with name: .newLabel doesn't exist".to_string())
+        result.map_err(|e| e.to_string()),
+        Err("Unable to parse line #2: undefined label .newLabel".to_string())
     );
 }
 
@@ -103,8 +106,8 @@ pub fn test_bad_variable_name() {
             .expect("Unable to convert temp file path to string"),
     );
     assert_eq!(
-        result,
-        Err("Unable to parse line #2: Invalid variable name 0x".to_string())
+        result.map_err(|e| e.to_string()),
+        Err("Unable to parse line #2, column 11: Invalid variable name 0x".to_string())
     );
 }
 
@@ -205,4 +208,786 @@ pub fn test_program_with_nested_loop() {
     assert!(result.is_ok());
     assert!(result.as_ref().ok().is_some());
     assert_eq!(result.ok().unwrap().unwrap() as u16, 110);
-}
\ No newline at end of file
+}
+
+#[test]
+pub fn test_program_with_spawned_task() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "SPAWN .worker
+        LOAD_VAL 10
+        SEND_CHANNEL input
+        RECV_CHANNEL output
+        RETURN_VALUE
+        .worker
+        RECV_CHANNEL input
+        LOAD_VAL 3
+        MULTIPLY
+        SEND_CHANNEL output";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    assert_eq!(result, Ok(Some(30)));
+}
+
+#[test]
+pub fn test_program_with_deadlock() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "SPAWN .worker
+        RECV_CHANNEL output
+        RETURN_VALUE
+        .worker
+        RECV_CHANNEL input
+        SEND_CHANNEL output";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    assert_eq!(
+        result.map_err(|e| e.to_string()),
+        Err("Runtime error: deadlock: all 2 tasks are blocked on RECV_CHANNEL".to_string())
+    );
+}
+
+#[test]
+pub fn test_program_with_function_calls() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 100
+        WRITE_VAR n
+        LOAD_VAL 3
+        CALL .square
+        LOAD_VAL 4
+        CALL .square
+        ADD
+        READ_VAR n
+        ADD
+        RETURN_VALUE
+        .square
+        WRITE_VAR n
+        READ_VAR n
+        READ_VAR n
+        MULTIPLY
+        RET";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    assert_eq!(result, Ok(Some(125)));
+}
+
+#[test]
+pub fn test_program_with_exceeded_call_depth() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        ".recursive
+        CALL .recursive";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let config = InterpreterConfig {
+        max_call_depth: 16,
+        ..InterpreterConfig::default()
+    };
+    let result = interpret_with_config(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+        config,
+    );
+    assert_eq!(
+        result.map_err(|e| e.to_string()),
+        Err("Runtime error: unable to process instruction #1 (line 2, column 9): maximum call depth of 16 exceeded".to_string())
+    );
+}
+
+#[test]
+pub fn test_program_with_overflow() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program = format!(
+        "LOAD_VAL {}
+        LOAD_VAL 2
+        ADD
+        RETURN_VALUE",
+        VariableValue::MAX
+    );
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let result = interpret(path);
+    assert_eq!(
+        result.map_err(|e| e.to_string()),
+        Err("Runtime error: unable to process instruction #3 (line 3, column 9): ADD overflow".to_string())
+    );
+
+    let config = InterpreterConfig {
+        overflow_mode: OverflowMode::Wrapping,
+        ..InterpreterConfig::default()
+    };
+    let result = interpret_with_config(path, config);
+    assert_eq!(result, Ok(Some(VariableValue::MAX.wrapping_add(2))));
+
+    let config = InterpreterConfig {
+        overflow_mode: OverflowMode::Saturating,
+        ..InterpreterConfig::default()
+    };
+    let result = interpret_with_config(path, config);
+    assert_eq!(result, Ok(Some(VariableValue::MAX)));
+}
+
+#[test]
+pub fn test_program_with_countdown_loop() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 10
+        WRITE_VAR i
+        LOAD_VAL 0
+        WRITE_VAR sum
+        .loop
+        READ_VAR i
+        READ_VAR sum
+        ADD
+        WRITE_VAR sum
+        LOAD_VAL 1
+        READ_VAR i
+        SUB
+        DUP
+        WRITE_VAR i
+        GOTO .loop
+        READ_VAR sum
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    assert_eq!(result, Ok(Some(55)));
+}
+
+#[test]
+pub fn test_program_with_division_by_zero() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 0
+        LOAD_VAL 10
+        MOD
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    assert_eq!(
+        result.map_err(|e| e.to_string()),
+        Err("Runtime error: unable to process instruction #3 (line 3, column 9): MOD division by zero".to_string())
+    );
+}
+
+#[test]
+pub fn test_program_with_bit_flags() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 0
+        WRITE_VAR flags
+        LOAD_VAL 2
+        LOAD_VAL 1
+        SHL
+        READ_VAR flags
+        OR
+        WRITE_VAR flags
+        LOAD_VAL 0
+        LOAD_VAL 1
+        SHL
+        READ_VAR flags
+        OR
+        WRITE_VAR flags
+        LOAD_VAL 1
+        NOT
+        READ_VAR flags
+        AND
+        LOAD_VAL 200
+        LOAD_VAL 1
+        SHL
+        XOR
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    assert_eq!(result, Ok(Some(4)));
+}
+
+#[test]
+pub fn test_program_with_jumps() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 0
+        WRITE_VAR i
+        .loop
+        LOAD_VAL 5
+        READ_VAR i
+        LESS
+        JUMP_IF_FALSE .end
+        LOAD_VAL 1
+        READ_VAR i
+        ADD
+        WRITE_VAR i
+        JUMP .loop
+        .end
+        READ_VAR i
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    assert_eq!(result, Ok(Some(5)));
+}
+
+#[test]
+pub fn test_read_of_unset_variable() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 1
+        WRITE_VAR x
+        READ_VAR x
+        READ_VAR y
+        ADD
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    assert_eq!(
+        result.map_err(|e| e.to_string()),
+        Err("Runtime error: unable to get variable y: doesn't exist, instruction#4 (line 4, column 9)".to_string())
+    );
+}
+
+#[test]
+pub fn test_program_with_infinite_loop() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "SPAWN .worker
+        .loop
+        LOAD_VAL 1
+        GOTO .loop
+        .worker
+        JUMP .worker";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+
+    let config = InterpreterConfig {
+        max_instructions: Some(1000),
+        ..InterpreterConfig::default()
+    };
+    let result = interpret_with_config(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+        config,
+    );
+    assert_eq!(
+        result.map_err(|e| e.to_string()),
+        Err("Runtime error: instruction budget exhausted after 1000 steps, next instruction #3 (line 4, column 9)".to_string())
+    );
+}
+
+#[test]
+pub fn test_program_gas_report() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 6
+        LOAD_VAL 7
+        MULTIPLY
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let report = run_with_config(path, InterpreterConfig::default()).expect("Program should run");
+    assert_eq!(report.value, Some(42));
+    assert_eq!(report.gas_used, 6);
+
+    let mut gas_costs = GasCosts::new(2);
+    gas_costs.set_cost(InstructionName::MULTIPLY, 5);
+    let config = InterpreterConfig {
+        gas_limit: Some(8),
+        gas_costs,
+        ..InterpreterConfig::default()
+    };
+    let result = run_with_config(path, config);
+    assert_eq!(
+        result.map_err(|e| e.to_string()),
+        Err("Runtime error: unable to process instruction #3 (line 3, column 9): out of gas, 4 of 8 used, MULTIPLY costs 5".to_string())
+    );
+}
+
+#[test]
+pub fn test_program_with_resource_limits() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 1
+        WRITE_VAR x
+        LOAD_VAL 2
+        WRITE_VAR y
+        LOAD_VAL 3
+        LOAD_VAL 4
+        ADD
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let config = InterpreterConfig {
+        max_variables: 1,
+        ..InterpreterConfig::default()
+    };
+    assert_eq!(
+        interpret_with_config(path, config).map_err(|e| e.to_string()),
+        Err("Runtime error: unable to process instruction #4 (line 4, column 9): maximum number of 1 variables exceeded".to_string())
+    );
+
+    let config = InterpreterConfig {
+        max_stack_depth: 1,
+        ..InterpreterConfig::default()
+    };
+    assert_eq!(
+        interpret_with_config(path, config).map_err(|e| e.to_string()),
+        Err("Runtime error: unable to process instruction #6 (line 6, column 9): maximum stack depth of 1 exceeded".to_string())
+    );
+
+    let config = InterpreterConfig {
+        max_stack_depth: 2,
+        max_variables: 2,
+        ..InterpreterConfig::default()
+    };
+    assert_eq!(interpret_with_config(path, config), Ok(Some(7)));
+}
+
+#[test]
+pub fn test_program_error_kinds() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    write!(file, "LOAD_VAL 1\nPUSH 2").expect("Unable to write to temp file");
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    match result {
+        Err(Error::Parse(errors)) => {
+            assert_eq!(errors.errors()[0].line, Some(2));
+            assert_eq!(errors.errors()[0].kind, ParseErrorKind::UnknownInstruction("PUSH".to_string()));
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    write!(file, "LOAD_VAL 1\nADD\nRETURN_VALUE").expect("Unable to write to temp file");
+    let result = interpret(
+        file.path()
+            .to_str()
+            .expect("Unable to convert temp file path to string"),
+    );
+    match result {
+        Err(Error::Runtime(error)) => {
+            assert_eq!(error.instruction_index, Some(1));
+            assert_eq!(error.instruction, Some(InstructionName::ADD));
+            assert_eq!(error.kind, RuntimeErrorKind::StackUnderflow);
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+pub fn test_program_with_several_parse_errors() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 1
+        WRITE_VAR 1x
+        .loop
+        READ_VAR x y
+        GOTO .loop
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let (bytecode, errors) = ByteCode::parse_file_recovering(path);
+    assert_eq!(
+        errors.iter().map(|error| error.to_string()).collect::<Vec<_>>(),
+        vec![
+            "Unable to parse line #2, column 19: Invalid variable name 1x".to_string(),
+            "Unable to parse line #4, column 20: Error creating read instruction: expected 1 argument, got 2".to_string(),
+        ]
+    );
+    assert_eq!(bytecode.instructions().len(), 3);
+
+    assert_eq!(
+        interpret(path).map_err(|e| e.to_string()),
+        Err("Unable to parse line #2, column 19: Invalid variable name 1x".to_string())
+    );
+}
+
+#[test]
+pub fn test_verify_program_stack() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 5
+        CALL .fact
+        RETURN_VALUE
+        .fact
+        DUP
+        LOAD_VAL 1
+        LESS
+        JUMP_IF_TRUE .recurse
+        RET
+        .recurse
+        WRITE_VAR n
+        READ_VAR n
+        LOAD_VAL 1
+        READ_VAR n
+        SUB
+        CALL .fact
+        MULTIPLY
+        RET";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let bytecode = ByteCode::parse_file(path).expect("Program should parse");
+    assert_eq!(verify_stack(&bytecode), Ok(()));
+    assert_eq!(interpret(path), Ok(Some(120)));
+
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 1
+        JUMP_IF_TRUE .skip
+        LOAD_VAL 2
+        .skip
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let bytecode = ByteCode::parse_file(path).expect("Program should parse");
+    let errors = verify_stack(&bytecode).expect_err("Program can underflow");
+    assert_eq!(
+        errors.iter().map(|error| error.to_string()).collect::<Vec<_>>(),
+        vec![
+            "Verification error: instruction #4 (line 5, column 9): RETURN needs 1 values on the stack, there can be 0".to_string(),
+            "Verification error: instruction #4 (line 5, column 9): reached with 0 and 1 values on the stack from different paths".to_string(),
+        ]
+    );
+}
+
+#[test]
+pub fn test_check_program_variables() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 1
+        WRITE_VAR unused
+        LOAD_VAL 1
+        JUMP_IF_TRUE .skip
+        LOAD_VAL 2
+        WRITE_VAR x
+        .skip
+        READ_VAR x
+        READ_VAR y
+        ADD
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let bytecode = ByteCode::parse_file(path).expect("Program should parse");
+    assert_eq!(
+        check_variables(&bytecode).iter().map(|diagnostic| diagnostic.to_string()).collect::<Vec<_>>(),
+        vec![
+            "warning: instruction #2 (line 2, column 9): variable unused is written but never read".to_string(),
+            "warning: instruction #7 (line 8, column 9): variable x may be read before it is written".to_string(),
+            "error: instruction #8 (line 9, column 9): variable y is read before it is written".to_string(),
+        ]
+    );
+}
+
+#[test]
+pub fn test_control_flow_graph() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 3
+        WRITE_VAR i
+        .loop
+        READ_VAR i
+        JUMP_IF_FALSE .end
+        READ_VAR i
+        LOAD_VAL 1
+        READ_VAR i
+        SUB
+        WRITE_VAR i
+        JUMP .loop
+        .end
+        READ_VAR i
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let bytecode = ByteCode::parse_file(path).expect("Program should parse");
+    let graph = ControlFlowGraph::new(&bytecode);
+    assert_eq!(graph.blocks.len(), 4);
+    assert_eq!(graph.loop_headers().into_iter().collect::<Vec<_>>(), vec![1]);
+    assert_eq!(graph.blocks[1].labels, vec![".loop".to_string()]);
+    let dot = graph.to_dot(&bytecode);
+    assert!(dot.contains("block1 [label=\".loop\\l#3  READ_VAR i\\l#4  JUMP_IF_FALSE .end\\l\", peripheries=2];"));
+    assert!(dot.contains("block2 -> block1 [label=\"taken\"];"));
+    assert_eq!(interpret(path), Ok(Some(0)));
+}
+
+#[test]
+pub fn test_optimize_program() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 2
+        LOAD_VAL 10
+        SUB
+        WRITE_VAR x
+        LOAD_VAL 1
+        JUMP_IF_FALSE .end
+        READ_VAR x
+        DUP
+        POP
+        LOAD_VAL 3
+        MULTIPLY
+        JUMP .end
+        LOAD_VAL 7
+        .end
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let bytecode = ByteCode::parse_file(path).expect("Program should parse");
+    let optimized = optimize(&bytecode);
+    assert_eq!(
+        optimized.instructions().iter().map(|instruction| instruction.to_string()).collect::<Vec<_>>(),
+        vec!["LOAD_VAL 8", "WRITE_VAR x", "READ_VAR x", "LOAD_VAL 3", "MULTIPLY", "RETURN_VALUE"]
+    );
+    assert_eq!(optimized.labels()[".end"], 5);
+    assert_eq!(interpret(path), Ok(Some(24)));
+    assert_eq!(run_with_config(path, InterpreterConfig::default()).map(|report| report.value), Ok(Some(24)));
+}
+
+#[test]
+pub fn test_binary_program() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 4
+        CALL .square
+        RETURN_VALUE
+        .square
+        DUP
+        MULTIPLY
+        RET";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+    let binary_file = NamedTempFile::new().expect("Unable to create temp file");
+    let binary_path = binary_file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let bytecode = ByteCode::parse_file(path).expect("Program should parse");
+    bytecode.write_binary_file(binary_path).expect("Unable to write binary file");
+    let loaded = ByteCode::load_binary_file(binary_path).expect("Binary file should load");
+    assert_eq!(loaded, bytecode);
+    assert_eq!(Interpreter::new(loaded).interpret(), Ok(Some(16)));
+
+    let error = ByteCode::load_binary_file(path).expect_err("Text file is not binary");
+    assert_eq!(error.kind, LoadErrorKind::InvalidMagic);
+    assert_eq!(error.to_string(), "Unable to load bytecode: not a binary ByteCode file");
+}
+
+#[test]
+pub fn test_disassemble_program() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 2
+        SPAWN .worker
+        RECV_CHANNEL result
+        RETURN_VALUE
+        .worker
+            LOAD_VAL 21
+            MULTIPLY
+            SEND_CHANNEL result";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let bytecode = ByteCode::parse_file(path).expect("Program should parse");
+    let loaded = ByteCode::from_binary(&bytecode.to_binary()).expect("Binary program should load");
+    let text = disassemble(&loaded, DisassemblyOptions::default());
+    assert_eq!(
+        text,
+        "    LOAD_VAL 2\n    SPAWN .worker\n    RECV_CHANNEL result\n    RETURN_VALUE\n\
+        .worker\n    LOAD_VAL 21\n    MULTIPLY\n    SEND_CHANNEL result\n"
+    );
+    let mut disassembled = NamedTempFile::new().expect("Unable to create temp file");
+    write!(disassembled, "{}", text).expect("Unable to write to temp file");
+    let disassembled_path = disassembled.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+    assert_eq!(ByteCode::parse_file(disassembled_path), Ok(bytecode));
+}
+
+#[test]
+pub fn test_format_program() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "load_val 3
+        .loop
+        DUP
+        JUMP_IF_FALSE   .end
+
+
+        WRITE_VAR n
+        LOAD_VAL 1
+        READ_VAR n
+        SUB
+        JUMP .loop
+        .end
+        RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let formatted = format_file(path).expect("Program should format");
+    assert_eq!(
+        formatted,
+        "    LOAD_VAL 3\n.loop\n    DUP\n    JUMP_IF_FALSE .end\n\n    WRITE_VAR n\n    LOAD_VAL 1\n    READ_VAR n\n    SUB\n    JUMP .loop\n\
+        .end\n    RETURN_VALUE\n"
+    );
+    assert_eq!(is_formatted(program), Ok(false));
+    assert_eq!(is_formatted(&formatted), Ok(true));
+
+    let mut formatted_file = NamedTempFile::new().expect("Unable to create temp file");
+    write!(formatted_file, "{}", formatted).expect("Unable to write to temp file");
+    let formatted_path = formatted_file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+    assert_eq!(interpret(formatted_path), Ok(Some(0)));
+
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    write!(file, "LOAD_VAL\nPUSH 1").expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+    assert_eq!(
+        format_file(path).map_err(|e| e.to_string()),
+        Err("Unable to parse line #1, column 9: Error creating load instruction: expected 1 argument, got 0\n\
+            Unable to parse line #2, column 1: Unknown instruction: PUSH".to_string())
+    );
+}
+
+#[test]
+pub fn test_program_with_comments() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "; squares a number
+        LOAD_VAL 7 # the number
+        CALL .square
+        RETURN_VALUE
+        .square ; x -> x * x
+            DUP
+            MULTIPLY
+            RET";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    assert_eq!(interpret(path), Ok(Some(49)));
+    let bytecode = ByteCode::parse_file(path).expect("Program should parse");
+    assert_eq!(
+        bytecode.comments().iter().map(|comment| (comment.line, comment.text.as_str())).collect::<Vec<_>>(),
+        vec![(1, "; squares a number"), (2, "# the number"), (5, "; x -> x * x")]
+    );
+    assert_eq!(
+        format_file(path),
+        Ok("; squares a number\n    LOAD_VAL 7 # the number\n    CALL .square\n    RETURN_VALUE\n\
+            .square ; x -> x * x\n    DUP\n    MULTIPLY\n    RET\n".to_string())
+    );
+    assert_eq!(disassemble(&bytecode, DisassemblyOptions::default()), format_file(path).unwrap());
+
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    write!(file, "LOAD_VAL 1 ; one\nADD 2 ; two").expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+    assert_eq!(
+        interpret(path).map_err(|e| e.to_string()),
+        Err("Unable to parse line #2, column 5: Error creating add instruction: expected 0 argument, got 1".to_string())
+    );
+}
+
+#[test]
+pub fn test_program_with_invalid_tokens() {
+    let mut file = NamedTempFile::new().expect("Unable to create temp file");
+    let program =
+        "LOAD_VAL 1
+        WRITE_VAR x-y
+        .a!b
+        .end RETURN_VALUE";
+    write!(file, "{}", program).expect("Unable to write to temp file");
+    let path = file.path()
+        .to_str()
+        .expect("Unable to convert temp file path to string");
+
+    let (_, errors) = ByteCode::parse_file_recovering(path);
+    assert_eq!(
+        errors.iter().map(|error| error.to_string()).collect::<Vec<_>>(),
+        vec![
+            "Unable to parse line #2, column 19: Invalid variable name x-y".to_string(),
+            "Unable to parse line #3, column 9: Invalid label name: .a!b".to_string(),
+            "Unable to parse line #4, column 14: Unexpected token after label: RETURN_VALUE".to_string(),
+        ]
+    );
+    assert_eq!(
+        interpret(path).map_err(|e| e.to_string()),
+        Err("Unable to parse line #2, column 19: Invalid variable name x-y".to_string())
+    );
+}